@@ -105,14 +105,14 @@ fn main() -> ! {
         0b00000, // seventh
         0b00000, // bottom line
     ];
-    let _ = lcd.create_custom_char(0x0, &o_symbol);
+    let degree = lcd.alloc_glyph(&o_symbol).unwrap();
 
     let _ = lcd.locate(1, 1);
     alarm0.schedule(2.secs()).unwrap();
     while !alarm0.finished() {}
 
     let _ = lcd.write("C1: 4.12 ");
-    let _ = lcd.write_special_char(0x00);
+    let _ = lcd.write_special_char(&degree);
     let _ = lcd.write("C");
     alarm0.schedule(2.secs()).unwrap();
     while !alarm0.finished() {}
@@ -0,0 +1,382 @@
+//! Custom glyphs in the 8 CGRAM slots.
+//!
+//! [`CgramAllocator`] hands out the slots for `SSD18030::alloc_glyph`, so widgets drawing their own
+//! glyphs do not overwrite each other. A slot stays taken until every [`GlyphHandle`] to it has been
+//! released, and identical bitmaps share one slot.
+//!
+//! [`GlyphCache`] backs the fallback glyphs of `SSD18030::enable_fallback_glyphs`: characters that
+//! are missing from the ROM get a bitmap from a table supplied by the application, which is loaded
+//! into a free slot or into the least recently used one. Slots held by handles are left alone.
+
+/// Number of custom glyphs the controller holds
+pub const CGRAM_SLOTS: usize = 8;
+
+/// Bitmap of a 5x8 glyph, one byte per row with the pixels in the low 5 bits
+pub type Glyph = [u8; 8];
+
+/// Bit B1 of a glyph row, which makes the row blink while data blink (BE) is enabled
+pub const ROW_BLINK: u8 = 0x80;
+
+/// `glyph` with every row blinking while data blink is enabled, see
+/// [`SSD18030::set_data_blink`](crate::ssd18030_i2c::SSD18030::set_data_blink)
+pub const fn blinking(glyph: &Glyph) -> Glyph {
+    let mut blinking = *glyph;
+    let mut row = 0;
+    while row < blinking.len() {
+        blinking[row] |= ROW_BLINK;
+        row += 1;
+    }
+    blinking
+}
+
+/// DDRAM addresses of the cells showing a glyph, one bit per address
+pub type CellMask = u128;
+
+/// Slot of a glyph taken with `SSD18030::alloc_glyph`. Give it back with
+/// `SSD18030::release_glyph`, a dropped handle keeps the slot taken.
+#[must_use]
+#[derive(Debug, PartialEq, Eq)]
+pub struct GlyphHandle {
+    slot: u8,
+}
+
+impl GlyphHandle {
+    /// Character code showing the glyph
+    pub fn code(&self) -> u8 {
+        self.slot
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AllocatedGlyph {
+    glyph: Glyph,
+
+    /// Handles to the slot that have not been released
+    refs: u16,
+}
+
+/// Reference counted CGRAM slots
+#[derive(Debug, Clone, Default)]
+pub struct CgramAllocator {
+    slots: [Option<AllocatedGlyph>; CGRAM_SLOTS],
+}
+
+impl CgramAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle to a slot showing `glyph`, along with whether the glyph still has to be written to
+    /// it. A free slot outside of `avoid` (one bit per slot) is picked before the others. `None` if
+    /// all slots hold other glyphs.
+    pub fn alloc(&mut self, glyph: &Glyph, avoid: u8) -> Option<(GlyphHandle, bool)> {
+        let shared = self
+            .slots
+            .iter()
+            .position(|s| s.is_some_and(|s| s.glyph == *glyph));
+        if let Some(slot) = shared {
+            let allocated = self.slots[slot].as_mut().unwrap();
+            allocated.refs = allocated.refs.checked_add(1)?;
+            return Some((GlyphHandle { slot: slot as u8 }, false));
+        }
+
+        let free = |i: &usize| self.slots[*i].is_none();
+        let slot = (0..CGRAM_SLOTS)
+            .filter(free)
+            .find(|i| avoid & (1 << i) == 0)
+            .or_else(|| (0..CGRAM_SLOTS).find(free))?;
+        self.slots[slot] = Some(AllocatedGlyph {
+            glyph: *glyph,
+            refs: 1,
+        });
+        Some((GlyphHandle { slot: slot as u8 }, true))
+    }
+
+    /// Give back a handle, freeing its slot once no other handle refers to it
+    pub fn release(&mut self, handle: GlyphHandle) {
+        let slot = &mut self.slots[handle.slot as usize];
+        if let Some(allocated) = slot {
+            allocated.refs -= 1;
+            if allocated.refs == 0 {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Slots holding a glyph, one bit per slot
+    pub fn taken(&self) -> u8 {
+        mask(&self.slots)
+    }
+
+    /// Slots holding a glyph along with their bitmaps
+    pub fn glyphs(&self) -> impl Iterator<Item = (u8, &Glyph)> {
+        (0..)
+            .zip(&self.slots)
+            .filter_map(|(slot, s)| Some((slot, &s.as_ref()?.glyph)))
+    }
+}
+
+/// One bit for every slot that is `Some`
+fn mask<T>(slots: &[Option<T>; CGRAM_SLOTS]) -> u8 {
+    slots
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_some())
+        .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedGlyph {
+    c: char,
+
+    last_used: u32,
+
+    cells: CellMask,
+}
+
+/// Where the code for a character comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlyphSlot {
+    /// The glyph is already in the slot
+    Loaded(u8),
+    /// The glyph has to be written to the slot first
+    Upload(u8, &'static Glyph),
+}
+
+/// Fallback glyphs currently in CGRAM
+#[derive(Debug, Clone)]
+pub struct GlyphCache {
+    glyphs: &'static [(char, Glyph)],
+
+    slots: [Option<CachedGlyph>; CGRAM_SLOTS],
+
+    /// Counter ordering the uses of the slots
+    tick: u32,
+
+    /// Cells whose glyph was replaced since the last [`GlyphCache::take_stale`]
+    stale: CellMask,
+}
+
+impl GlyphCache {
+    pub fn new(glyphs: &'static [(char, Glyph)]) -> Self {
+        Self {
+            glyphs,
+            slots: [None; CGRAM_SLOTS],
+            tick: 0,
+            stale: 0,
+        }
+    }
+
+    /// Slot for `c`, evicting the least recently used glyph if all slots are taken. Slots in
+    /// `reserved` (one bit per slot) are not used. `None` if the table has no glyph for `c` or all
+    /// slots are reserved.
+    pub fn slot(&mut self, c: char, reserved: u8) -> Option<GlyphSlot> {
+        let (_, glyph) = self
+            .glyphs
+            .iter()
+            .find(|(glyph_char, _)| *glyph_char == c)?;
+        self.tick = self.tick.wrapping_add(1);
+
+        if let Some(slot) = self.slots.iter().position(|s| s.is_some_and(|s| s.c == c)) {
+            self.slots[slot].as_mut().unwrap().last_used = self.tick;
+            return Some(GlyphSlot::Loaded(slot as u8));
+        }
+
+        let usable = |i: &usize| reserved & (1 << i) == 0;
+        let slot = match (0..CGRAM_SLOTS)
+            .filter(usable)
+            .find(|&i| self.slots[i].is_none())
+        {
+            Some(free) => free,
+            None => self.least_recently_used(reserved)?,
+        };
+        if let Some(evicted) = self.slots[slot] {
+            self.stale |= evicted.cells;
+        }
+        self.slots[slot] = Some(CachedGlyph {
+            c,
+            last_used: self.tick,
+            cells: 0,
+        });
+        Some(GlyphSlot::Upload(slot as u8, glyph))
+    }
+
+    fn least_recently_used(&self, reserved: u8) -> Option<usize> {
+        (0..CGRAM_SLOTS)
+            .filter(|i| reserved & (1 << i) == 0)
+            .max_by_key(|&i| self.slots[i].map_or(0, |slot| self.tick.wrapping_sub(slot.last_used)))
+    }
+
+    /// Slots holding a glyph, one bit per slot
+    pub fn loaded(&self) -> u8 {
+        mask(&self.slots)
+    }
+
+    /// Record that `code` was written to DDRAM `address`
+    pub fn written(&mut self, address: u8, code: u8) {
+        let bit = 1 << (address & 0x7F);
+        self.stale &= !bit;
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            if let Some(slot) = slot {
+                // Codes 0x08-0x0F show the same glyphs as 0x00-0x07
+                if code < 0x10 && (code & 0x07) as usize == i {
+                    slot.cells |= bit;
+                } else {
+                    slot.cells &= !bit;
+                }
+            }
+        }
+    }
+
+    /// Give up `slot` after something else was written to it, its cells become stale
+    pub fn evict(&mut self, slot: u8) {
+        if let Some(evicted) = self.slots[slot as usize & 0x07].take() {
+            self.stale |= evicted.cells;
+        }
+    }
+
+    /// Forget all cells after the display was cleared
+    pub fn cleared(&mut self) {
+        self.stale = 0;
+        for slot in self.slots.iter_mut().flatten() {
+            slot.cells = 0;
+        }
+    }
+
+    /// Forget all glyphs after the controller was reset, which wiped CGRAM
+    pub fn reset(&mut self) {
+        self.slots = [None; CGRAM_SLOTS];
+        self.stale = 0;
+    }
+
+    /// Cells showing a glyph that has since been evicted, cleared by the call
+    pub fn take_stale(&mut self) -> CellMask {
+        core::mem::take(&mut self.stale)
+    }
+}
+
+/// Visible cells of a [`CellMask`] as 1-based row and column, see
+/// `SSD18030::take_stale_cells`
+#[derive(Debug, Clone)]
+pub struct StaleCells {
+    mask: CellMask,
+
+    /// DDRAM address of the first visible column
+    offset: u8,
+
+    /// DDRAM line shown in each row
+    rows: &'static [u8],
+}
+
+impl StaleCells {
+    pub fn new(mask: CellMask, offset: u8, rows: &'static [u8]) -> Self {
+        Self { mask, offset, rows }
+    }
+}
+
+impl Iterator for StaleCells {
+    type Item = (u8, u8);
+
+    fn next(&mut self) -> Option<(u8, u8)> {
+        while self.mask != 0 {
+            let address = self.mask.trailing_zeros() as u8;
+            self.mask &= self.mask - 1;
+
+            let col = (address & 0x1F).wrapping_sub(self.offset);
+            let row = self.rows.iter().position(|&line| line == address & 0x60);
+            if let Some(row) = row
+                && col < 16
+            {
+                return Some((row as u8 + 1, col + 1));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLYPHS: &[(char, Glyph)] = &[
+        ('₀', [0; 8]),
+        ('₁', [1; 8]),
+        ('₂', [2; 8]),
+        ('₃', [3; 8]),
+        ('₄', [4; 8]),
+        ('₅', [5; 8]),
+        ('₆', [6; 8]),
+        ('₇', [7; 8]),
+        ('₈', [8; 8]),
+    ];
+
+    #[test]
+    fn test_reuse_and_lru_eviction() {
+        let mut cache = GlyphCache::new(GLYPHS);
+        assert_eq!(cache.slot('x', 0), None);
+
+        for (i, (c, glyph)) in GLYPHS[..8].iter().enumerate() {
+            assert_eq!(cache.slot(*c, 0), Some(GlyphSlot::Upload(i as u8, glyph)));
+        }
+        assert_eq!(cache.slot('₀', 0), Some(GlyphSlot::Loaded(0)));
+
+        // Slot 1 is now the least recently used one
+        assert_eq!(cache.slot('₈', 0), Some(GlyphSlot::Upload(1, &[8; 8])));
+    }
+
+    #[test]
+    fn test_reserved_slots() {
+        let mut cache = GlyphCache::new(GLYPHS);
+        assert_eq!(
+            cache.slot('₀', 0b0000_0011),
+            Some(GlyphSlot::Upload(2, &[0; 8]))
+        );
+        assert_eq!(cache.slot('₁', 0xFF), None);
+    }
+
+    #[test]
+    fn test_allocator_shares_and_frees() {
+        let mut cgram = CgramAllocator::new();
+        let (degree, upload) = cgram.alloc(&[1; 8], 0b0000_0001).unwrap();
+        assert_eq!((degree.code(), upload), (1, true));
+        let (shared, upload) = cgram.alloc(&[1; 8], 0).unwrap();
+        assert_eq!((shared.code(), upload), (1, false));
+
+        let mut others: [_; 7] = core::array::from_fn(|i| cgram.alloc(&[i as u8 + 2; 8], 0));
+        assert!(others.iter().all(Option::is_some));
+        assert_eq!(cgram.alloc(&[0xFF; 8], 0), None);
+
+        // The slot is only given up with the last handle
+        cgram.release(degree);
+        assert_eq!(cgram.alloc(&[0xFF; 8], 0), None);
+        cgram.release(shared);
+        let (bar, upload) = cgram.alloc(&[0xFF; 8], 0).unwrap();
+        assert_eq!((bar.code(), upload), (1, true));
+
+        cgram.release(others[0].take().unwrap().0);
+        assert_eq!(cgram.taken(), 0b1111_1110);
+    }
+
+    #[test]
+    fn test_stale_cells() {
+        let mut cache = GlyphCache::new(GLYPHS);
+        for (c, _) in &GLYPHS[..8] {
+            cache.slot(*c, 0);
+        }
+        cache.written(0x04, 0);
+        cache.written(0x05, 0);
+        cache.written(0x24, 0);
+        // Overwritten with ROM text, no longer showing the glyph
+        cache.written(0x05, b'a');
+
+        cache.slot('₈', 0);
+        let stale = cache.take_stale();
+        assert_eq!(stale, 1 << 0x04 | 1 << 0x24);
+        assert_eq!(cache.take_stale(), 0);
+
+        let mut cells = StaleCells::new(stale, 0x04, &[0x00, 0x20, 0x40, 0x60]);
+        assert_eq!(cells.next(), Some((1, 1)));
+        assert_eq!(cells.next(), Some((2, 1)));
+        assert_eq!(cells.next(), None);
+    }
+}
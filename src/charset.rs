@@ -0,0 +1,306 @@
+//! Mapping from Unicode to the codes of the character ROMs.
+//!
+//! ROM B shows printable ASCII apart from `~` at its own code, Cyrillic and symbols from 0x80 on
+//! and Latin-1 from 0xC0 on. ROM A keeps letters, digits and most punctuation at their ASCII
+//! codes, but shows national characters in place of `$`, `@`, `[`, `\`, `]`, `^`, `_`, the
+//! backtick, `{`, `|`, `}` and `~`. Its upper half holds accented letters, Greek and symbols.
+//! Codes 0x10-0x1F hold arrows and symbols in ROM A and ROM B. ROM C follows the HD44780 A00
+//! table, with `¥` in place of the backslash.
+use crate::commands::Rom;
+
+/// Symbols at 0x10-0x1F of ROM A and ROM B
+const SYMBOLS: &[(char, u8)] = &[
+    ('▶', 0x10),
+    ('◀', 0x11),
+    ('“', 0x12),
+    ('”', 0x13),
+    ('●', 0x16),
+    ('↑', 0x18),
+    ('↓', 0x19),
+    ('→', 0x1A),
+    ('←', 0x1B),
+    ('≤', 0x1C),
+    ('≥', 0x1D),
+    ('▲', 0x1E),
+    ('▼', 0x1F),
+];
+
+/// ASCII codes ROM A shows national characters for
+const ROM_A_REPLACED: &[u8] = b"$@[\\]^_`{|}~";
+
+/// Characters of ROM A outside of ASCII, the first code wins for characters it holds twice
+const ROM_A: &[(char, u8)] = &[
+    ('¤', 0x24),
+    ('¡', 0x40),
+    ('Ä', 0x5B),
+    ('Ö', 0x5C),
+    ('Ñ', 0x5D),
+    ('Ü', 0x5E),
+    ('§', 0x5F),
+    ('¿', 0x60),
+    ('ä', 0x7B),
+    ('ö', 0x7C),
+    ('ñ', 0x7D),
+    ('ü', 0x7E),
+    ('à', 0x7F),
+    ('Ç', 0x80),
+    ('é', 0x82),
+    ('â', 0x83),
+    ('å', 0x86),
+    ('ç', 0x87),
+    ('ê', 0x88),
+    ('ë', 0x89),
+    ('è', 0x8A),
+    ('ï', 0x8B),
+    ('î', 0x8C),
+    ('ì', 0x8D),
+    ('Å', 0x8F),
+    ('É', 0x90),
+    ('æ', 0x91),
+    ('Æ', 0x92),
+    ('ô', 0x93),
+    ('ò', 0x95),
+    ('û', 0x96),
+    ('ù', 0x97),
+    ('ÿ', 0x98),
+    ('ª', 0x9D),
+    ('º', 0x9E),
+    ('á', 0xA0),
+    ('í', 0xA1),
+    ('ó', 0xA2),
+    ('ú', 0xA3),
+    ('¢', 0xA4),
+    ('£', 0xA5),
+    ('¥', 0xA6),
+    ('ƒ', 0xA8),
+    ('Ã', 0xAA),
+    ('ã', 0xAB),
+    ('Õ', 0xAC),
+    ('õ', 0xAD),
+    ('Ø', 0xAE),
+    ('ø', 0xAF),
+    ('¨', 0xB1),
+    ('°', 0xB2),
+    ('`', 0xB3),
+    ('´', 0xB4),
+    ('½', 0xB5),
+    ('¼', 0xB6),
+    ('×', 0xB7),
+    ('÷', 0xB8),
+    ('≤', 0xB9),
+    ('≥', 0xBA),
+    ('«', 0xBB),
+    ('»', 0xBC),
+    ('≠', 0xBD),
+    ('√', 0xBE),
+    ('¯', 0xBF),
+    ('∞', 0xC2),
+    ('•', 0xCD),
+    ('®', 0xCE),
+    ('©', 0xCF),
+    ('™', 0xD0),
+    ('†', 0xD1),
+    ('¶', 0xD3),
+    ('Γ', 0xD4),
+    ('Δ', 0xD5),
+    ('Θ', 0xD6),
+    ('Λ', 0xD7),
+    ('Ξ', 0xD8),
+    ('Π', 0xD9),
+    ('Σ', 0xDA),
+    ('Φ', 0xDC),
+    ('Ψ', 0xDD),
+    ('Ω', 0xDE),
+    ('α', 0xDF),
+    ('β', 0xE0),
+    ('ß', 0xE0),
+    ('γ', 0xE1),
+    ('δ', 0xE2),
+    ('ε', 0xE3),
+    ('ζ', 0xE4),
+    ('η', 0xE5),
+    ('θ', 0xE6),
+    ('ι', 0xE7),
+    ('κ', 0xE8),
+    ('λ', 0xE9),
+    ('µ', 0xEA),
+    ('μ', 0xEA),
+    ('ν', 0xEB),
+    ('ξ', 0xEC),
+    ('π', 0xED),
+    ('ρ', 0xEE),
+    ('σ', 0xEF),
+    ('τ', 0xF0),
+    ('υ', 0xF1),
+    ('χ', 0xF2),
+    ('ψ', 0xF3),
+    ('ω', 0xF4),
+];
+
+/// Characters of ROM B between 0x80 and 0xBF. From 0xC0 on ROM B matches Latin-1.
+const ROM_B: &[(char, u8)] = &[
+    ('Б', 0x80),
+    ('Д', 0x81),
+    ('Ж', 0x82),
+    ('З', 0x83),
+    ('И', 0x84),
+    ('Й', 0x85),
+    ('Л', 0x86),
+    ('П', 0x87),
+    ('У', 0x88),
+    ('Ц', 0x89),
+    ('Ч', 0x8A),
+    ('Ш', 0x8B),
+    ('Щ', 0x8C),
+    ('Ъ', 0x8D),
+    ('Ы', 0x8E),
+    ('Э', 0x8F),
+    ('α', 0x90),
+    ('♪', 0x91),
+    ('Γ', 0x92),
+    ('π', 0x93),
+    ('Σ', 0x94),
+    ('σ', 0x95),
+    ('τ', 0x97),
+    ('Θ', 0x99),
+    ('Ω', 0x9A),
+    ('δ', 0x9B),
+    ('∞', 0x9C),
+    ('♥', 0x9D),
+    ('ε', 0x9E),
+    ('∩', 0x9F),
+    ('‖', 0xA0),
+    ('¡', 0xA1),
+    ('¢', 0xA2),
+    ('£', 0xA3),
+    ('¤', 0xA4),
+    ('¥', 0xA5),
+    ('¦', 0xA6),
+    ('§', 0xA7),
+    ('ƒ', 0xA8),
+    ('©', 0xA9),
+    ('ª', 0xAA),
+    ('«', 0xAB),
+    ('Ю', 0xAC),
+    ('Я', 0xAD),
+    ('®', 0xAE),
+    ('‘', 0xAF),
+    ('°', 0xB0),
+    ('±', 0xB1),
+    ('²', 0xB2),
+    ('³', 0xB3),
+    ('µ', 0xB5),
+    ('μ', 0xB5),
+    ('¶', 0xB6),
+    ('·', 0xB7),
+    ('ω', 0xB8),
+    ('¹', 0xB9),
+    ('º', 0xBA),
+    ('»', 0xBB),
+    ('¼', 0xBC),
+    ('½', 0xBD),
+    ('¾', 0xBE),
+    ('¿', 0xBF),
+];
+
+/// Characters of ROM C outside of ASCII
+const ROM_C: &[(char, u8)] = &[
+    ('¥', 0x5C),
+    ('→', 0x7E),
+    ('←', 0x7F),
+    ('·', 0xA5),
+    ('°', 0xDF),
+    ('α', 0xE0),
+    ('ä', 0xE1),
+    ('β', 0xE2),
+    ('ß', 0xE2),
+    ('ε', 0xE3),
+    ('µ', 0xE4),
+    ('μ', 0xE4),
+    ('σ', 0xE5),
+    ('ρ', 0xE6),
+    ('√', 0xE8),
+    ('¢', 0xEC),
+    ('ñ', 0xEE),
+    ('ö', 0xEF),
+    ('θ', 0xF2),
+    ('∞', 0xF3),
+    ('Ω', 0xF4),
+    ('ü', 0xF5),
+    ('Σ', 0xF6),
+    ('π', 0xF7),
+    ('÷', 0xFD),
+    ('█', 0xFF),
+];
+
+/// Code of `c` in `rom`, `None` if the ROM has no such glyph
+pub fn encode(rom: Rom, c: char) -> Option<u8> {
+    let replaced: &[u8] = match rom {
+        Rom::A => ROM_A_REPLACED,
+        Rom::B => b"~",
+        Rom::C => b"\\~",
+    };
+    if (' '..='~').contains(&c) && !replaced.contains(&(c as u8)) {
+        return Some(c as u8);
+    }
+    if rom == Rom::B && ('\u{C0}'..='\u{FF}').contains(&c) {
+        return Some(c as u32 as u8);
+    }
+
+    let tables: &[&[(char, u8)]] = match rom {
+        Rom::A => &[ROM_A, SYMBOLS],
+        Rom::B => &[ROM_B, SYMBOLS],
+        Rom::C => &[ROM_C],
+    };
+    tables
+        .iter()
+        .flat_map(|table| table.iter())
+        .find(|(glyph, _)| *glyph == c)
+        .map(|(_, code)| *code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_rom_a() {
+        assert_eq!(encode(Rom::A, 'A'), Some(0x41));
+        assert_eq!(encode(Rom::A, '#'), Some(0x23));
+        assert_eq!(encode(Rom::A, '\n'), None);
+        assert_eq!(encode(Rom::A, '$'), None);
+        assert_eq!(encode(Rom::A, '['), None);
+        assert_eq!(encode(Rom::A, '°'), Some(0xB2));
+        assert_eq!(encode(Rom::A, 'µ'), Some(0xEA));
+        assert_eq!(encode(Rom::A, 'ä'), Some(0x7B));
+        assert_eq!(encode(Rom::A, 'Ω'), Some(0xDE));
+        assert_eq!(encode(Rom::A, '`'), Some(0xB3));
+        assert_eq!(encode(Rom::A, '→'), Some(0x1A));
+    }
+
+    #[test]
+    fn test_encode_rom_b() {
+        assert_eq!(encode(Rom::B, '$'), Some(0x24));
+        assert_eq!(encode(Rom::B, '\\'), Some(0x5C));
+        assert_eq!(encode(Rom::B, '}'), Some(0x7D));
+        assert_eq!(encode(Rom::B, '~'), None);
+        assert_eq!(encode(Rom::B, '°'), Some(0xB0));
+        assert_eq!(encode(Rom::B, 'µ'), Some(0xB5));
+        assert_eq!(encode(Rom::B, 'ä'), Some(0xE4));
+        assert_eq!(encode(Rom::B, 'Ω'), Some(0x9A));
+        assert_eq!(encode(Rom::B, 'Я'), Some(0xAD));
+        assert_eq!(encode(Rom::B, '≥'), Some(0x1D));
+    }
+
+    #[test]
+    fn test_encode_rom_c() {
+        assert_eq!(encode(Rom::C, '$'), Some(0x24));
+        assert_eq!(encode(Rom::C, '°'), Some(0xDF));
+        assert_eq!(encode(Rom::C, 'µ'), Some(0xE4));
+        assert_eq!(encode(Rom::C, 'ä'), Some(0xE1));
+        assert_eq!(encode(Rom::C, 'Ω'), Some(0xF4));
+        assert_eq!(encode(Rom::C, '\\'), None);
+        assert_eq!(encode(Rom::C, '~'), None);
+        assert_eq!(encode(Rom::C, '▶'), None);
+    }
+}
@@ -2,6 +2,8 @@ use bitflags::bitflags;
 
 pub const MODE_COMMAND: u8 = 0x00;
 pub const MODE_DATA: u8 = 0x40;
+/// Co bit of the I2C control byte: another control byte follows after the next byte
+pub const MODE_CONTINUATION: u8 = 0x80;
 
 pub const CMD_CLEAR_DISPLAY: u8 = 0x01;
 pub const CMD_RETURN_HOME: u8 = 0x02;
@@ -12,10 +14,18 @@ pub const CMD_CURSOR_SHIFT_LEFT: u8 = 0x00;
 pub const CMD_CURSOR_SHIFT_RIGHT: u8 = 0x04;
 pub const CMD_SHIFT: u8 = 0x10;
 
+/// Busy flag in the status byte, the lower 7 bits hold the address counter
+pub const STATUS_BUSY: u8 = 0x80;
+/// Part ID read in place of the address counter while RE = 1
+pub const SSD1803A_PART_ID: u8 = 0x1A;
+
 pub const ADDR_CGRAM: u8 = 0x40;
 pub const ADDR_DDRAM: u8 = 0x80;
 pub const ADDR_DDRAM_TOP_OFFSET: u8 = 0x04;
 
+/// DL bit of the function set, cleared for a 4-bit parallel bus
+pub const FUNCTION_SET_DL: u8 = 0x10;
+
 /// RE = 1, IS = 0
 pub const CMD_8BIT_4LINES_RE1_IS0: u8 = 0x3A;
 /// RE = 0, IS = 1
@@ -43,6 +53,8 @@ pub const CMD_POWER_ICON_CONTRAST: u8 = 0x5C;
 pub const CMD_FOLLOWER_CONTROL_DOGS164: u8 = 0x6C;
 pub const CMD_FOLLOWER_CONTROL: u8 = 0x60;
 pub const CMD_ROM_SELECT: u8 = 0x72;
+/// Temperature coefficient control, followed by TC2-TC0 in a data byte
+pub const CMD_TEMP_COEFFICIENT: u8 = 0x76;
 
 pub const COMMAND_3LINES_TOP: u8 = 0x1F;
 pub const COMMAND_3LINES_MIDDLE: u8 = 0x17;
@@ -50,6 +62,7 @@ pub const COMMAND_3LINES_BOTTOM: u8 = 0x13;
 pub const COMMAND_2LINES: u8 = 0x1B;
 
 bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct DisplayConfig: u8 {
         const DISPLAY_ON = 0x04;
         const CURSOR_ON  = 0x02;
@@ -57,6 +70,21 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Lines taking part in display shift and dot scroll
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ShiftLines: u8 {
+        const LINE1 = 0x01;
+        const LINE2 = 0x02;
+        const LINE3 = 0x04;
+        const LINE4 = 0x08;
+    }
+}
+
+/// Largest horizontal dot scroll quantity
+pub const MAX_SCROLL_DOTS: u8 = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct EntryModeSettings {
     pub direction: HorizontalDir,
     pub shift_incr: bool,
@@ -81,6 +109,9 @@ impl EntryModeSettings {
     }
 }
 
+/// Segment and common scan direction relative to the view mode, `LeftToRight` and `TopToBottom`
+/// keep the scan of the view mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SegCommControl {
     pub seg_dir: HorizontalDir,
     pub com_dir: VerticalDir,
@@ -91,35 +122,41 @@ impl SegCommControl {
         Self { seg_dir, com_dir }
     }
 
-    pub fn cmd(&self) -> u8 {
+    /// View mode instruction (RE=1) with the BDS and BDC bits of `view_mode` mirrored for a
+    /// reversed segment or common scan direction
+    pub fn cmd(&self, view_mode: ViewMode) -> u8 {
         let seg_bit = match self.seg_dir {
-            HorizontalDir::LeftToRight => 0x01,
-            HorizontalDir::RightToLeft => 0x00,
+            HorizontalDir::LeftToRight => 0x00,
+            HorizontalDir::RightToLeft => 0x01, // BDS
         };
         let com_bit = match self.com_dir {
-            VerticalDir::TopToBottom => 0x20,
-            VerticalDir::BottomToTop => 0x00,
+            VerticalDir::TopToBottom => 0x00,
+            VerticalDir::BottomToTop => 0x02, // BDC
         };
 
-        0x40 | seg_bit | com_bit // SEG/COM direction control
+        view_mode as u8 ^ (seg_bit | com_bit)
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HorizontalDir {
     RightToLeft,
     LeftToRight,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VerticalDir {
     TopToBottom,
     BottomToTop,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShiftType {
     Display,
     Cursor,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ShiftSettings {
     pub mode: HorizontalDir,
     pub shift_type: ShiftType,
@@ -139,6 +176,24 @@ impl ShiftSettings {
     }
 }
 
+/// Busy flag and address counter read back from the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    pub busy: bool,
+
+    /// Address counter (AC6-AC0)
+    pub address: u8,
+}
+
+impl From<u8> for Status {
+    fn from(byte: u8) -> Self {
+        Self {
+            busy: byte & STATUS_BUSY != 0,
+            address: byte & !STATUS_BUSY,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ViewMode {
@@ -155,7 +210,7 @@ pub enum DoubleHeight {
     Lines3Bottom = 0,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Rom {
     A = 0x00,
@@ -163,6 +218,7 @@ pub enum Rom {
     C = 0x08,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PowerIconContrast {
     pub booster: bool,
     pub icon: bool,   // 0-7
@@ -209,6 +265,7 @@ impl PowerIconContrast {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FollowerControl {
     pub rab: Rab,
     pub d_on: bool,
@@ -257,6 +314,7 @@ pub enum Rab {
     IR7_6p5 = 0b111,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ExtendedFunctionSet {
     /// FW bit
     font_width: FontWidth,
@@ -308,6 +366,7 @@ impl Default for ExtendedFunctionSet {
 }
 
 /// Function set command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DisplaySettings {
     /// N Bit
     pub line_number_control: LineDisplayMode,
@@ -385,6 +444,7 @@ impl DisplaySettings {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DoubleHeightBiasDisplayShift {
     pub double_height_mode: DoubleHeight,
 
@@ -422,6 +482,7 @@ impl DoubleHeightBiasDisplayShift {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct OscillatorSettings {
     pub freq: OscillatorFreq,
     pub bs0: bool,
@@ -456,6 +517,27 @@ impl OscillatorSettings {
     }
 }
 
+/// LCD bias ratio, selected by the BS1 (RE=1, IS=0) and BS0 (RE=0, IS=1) bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum Bias {
+    OneFifth = 0b00,
+    OneFourth = 0b01,
+    OneSeventh = 0b10,
+    #[default]
+    OneSixth = 0b11,
+}
+
+impl Bias {
+    pub fn bs1(&self) -> bool {
+        *self as u8 & 0b10 != 0
+    }
+
+    pub fn bs0(&self) -> bool {
+        *self as u8 & 0b01 != 0
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[repr(u8)]
 pub enum FontWidth {
@@ -470,6 +552,13 @@ pub enum LineDisplayMode {
     TwoOrFourLines = 0b1,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataLength {
+    FourBit = 0b0,
+    #[default]
+    EightBit = 0b1,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OscillatorFreq {
     Freq680kHz = 0b111,
@@ -482,6 +571,7 @@ pub enum OscillatorFreq {
     Freq420kHz = 0b000,
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -500,10 +590,14 @@ mod tests {
     #[test]
     fn test_seg_comm_control() {
         let scc = SegCommControl::new(HorizontalDir::LeftToRight, VerticalDir::TopToBottom);
-        assert_eq!(scc.cmd(), 0x61);
+        assert_eq!(scc.cmd(ViewMode::Top), 0x05);
+        assert_eq!(scc.cmd(ViewMode::Bottom), 0x06);
 
         let scc = SegCommControl::new(HorizontalDir::RightToLeft, VerticalDir::BottomToTop);
-        assert_eq!(scc.cmd(), 0x40);
+        assert_eq!(scc.cmd(ViewMode::Top), 0x06);
+
+        let scc = SegCommControl::new(HorizontalDir::RightToLeft, VerticalDir::TopToBottom);
+        assert_eq!(scc.cmd(ViewMode::Bottom), 0x07);
     }
 
     #[test]
@@ -556,6 +650,29 @@ mod tests {
         assert_eq!(fc.cmd(), 0x60);
     }
 
+    #[test]
+    fn test_status() {
+        let status = Status::from(0x85);
+        assert!(status.busy);
+        assert_eq!(status.address, 0x05);
+        let status = Status::from(0x24);
+        assert!(!status.busy);
+        assert_eq!(status.address, 0x24);
+    }
+
+    #[test]
+    fn test_bias_bits() {
+        assert_eq!((Bias::OneSixth.bs1(), Bias::OneSixth.bs0()), (true, true));
+        assert_eq!(
+            (Bias::OneFourth.bs1(), Bias::OneFourth.bs0()),
+            (false, true)
+        );
+        assert_eq!(
+            (Bias::OneSeventh.bs1(), Bias::OneSeventh.bs0()),
+            (true, false)
+        );
+    }
+
     #[test]
     fn test_osc_freq_cmd() {
         let ofc = OscillatorSettings::new(OscillatorFreq::Freq540kHz, true);
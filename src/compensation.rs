@@ -0,0 +1,135 @@
+//! Temperature compensation of the contrast.
+//!
+//! The application reads a temperature sensor and hands the value to
+//! `SSD18030::compensate_contrast`, which sets the contrast picked by a [`ContrastCurve`]. Readings
+//! are only acted upon once they moved by the hysteresis, so a sensor jittering around a step of
+//! the curve does not make the display flicker.
+
+/// Contrast (0-63) as a function of the temperature in °C
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContrastCurve<'a> {
+    /// `contrast` at `temperature`, changing by `steps_per_10c` for every 10 °C above it
+    Linear {
+        temperature: i16,
+        contrast: u8,
+        steps_per_10c: i16,
+    },
+
+    /// Points of (temperature, contrast) sorted by temperature. Values in between are
+    /// interpolated, values outside are taken from the nearest point.
+    Table(&'a [(i16, u8)]),
+}
+
+impl ContrastCurve<'_> {
+    /// Contrast for `temperature`, `None` for an empty table
+    pub fn contrast(&self, temperature: i16) -> Option<u8> {
+        let contrast = match *self {
+            ContrastCurve::Linear {
+                temperature: reference,
+                contrast,
+                steps_per_10c,
+            } => {
+                contrast as i32
+                    + (temperature as i32 - reference as i32) * steps_per_10c as i32 / 10
+            }
+            ContrastCurve::Table(points) => {
+                let (first, last) = (points.first()?, points.last()?);
+                if temperature <= first.0 {
+                    first.1 as i32
+                } else if temperature >= last.0 {
+                    last.1 as i32
+                } else {
+                    let upper = points.iter().position(|p| p.0 >= temperature)?;
+                    let ((t0, c0), (t1, c1)) = (points[upper - 1], points[upper]);
+                    let (t0, c0, t1, c1) = (t0 as i32, c0 as i32, t1 as i32, c1 as i32);
+                    c0 + (temperature as i32 - t0) * (c1 - c0) / (t1 - t0)
+                }
+            }
+        };
+
+        Some(contrast.clamp(0, 63) as u8)
+    }
+}
+
+/// State of the compensation between two readings
+#[derive(Debug, Clone, Copy)]
+pub struct TemperatureCompensation<'a> {
+    curve: ContrastCurve<'a>,
+
+    /// Change in °C needed before the contrast is recalculated
+    hysteresis: u16,
+
+    /// Temperature the contrast was last calculated for
+    last: Option<i16>,
+}
+
+impl<'a> TemperatureCompensation<'a> {
+    pub fn new(curve: ContrastCurve<'a>, hysteresis: u16) -> Self {
+        Self {
+            curve,
+            hysteresis,
+            last: None,
+        }
+    }
+
+    /// Contrast for a new reading, or `None` while the temperature stays within the hysteresis of
+    /// the last one acted upon. The reading counts as acted upon only after [`Self::accept`].
+    pub fn contrast(&self, temperature: i16) -> Option<u8> {
+        if let Some(last) = self.last
+            && temperature.abs_diff(last) < self.hysteresis
+        {
+            return None;
+        }
+
+        self.curve.contrast(temperature)
+    }
+
+    /// Note that the contrast for `temperature` was set, so readings close to it are ignored
+    pub fn accept(&mut self, temperature: i16) {
+        self.last = Some(temperature);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_curve() {
+        let curve = ContrastCurve::Linear {
+            temperature: 25,
+            contrast: 40,
+            steps_per_10c: -3,
+        };
+        assert_eq!(curve.contrast(25), Some(40));
+        assert_eq!(curve.contrast(-15), Some(52));
+        assert_eq!(curve.contrast(55), Some(31));
+        assert_eq!(curve.contrast(-300), Some(63));
+    }
+
+    #[test]
+    fn test_table_curve() {
+        let curve = ContrastCurve::Table(&[(-20, 50), (20, 42), (60, 30)]);
+        assert_eq!(curve.contrast(-40), Some(50));
+        assert_eq!(curve.contrast(0), Some(46));
+        assert_eq!(curve.contrast(20), Some(42));
+        assert_eq!(curve.contrast(40), Some(36));
+        assert_eq!(curve.contrast(80), Some(30));
+        assert_eq!(ContrastCurve::Table(&[]).contrast(0), None);
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let curve = ContrastCurve::Table(&[(0, 40), (40, 20)]);
+        let mut compensation = TemperatureCompensation::new(curve, 2);
+        assert_eq!(compensation.contrast(20), Some(30));
+        compensation.accept(20);
+        assert_eq!(compensation.contrast(21), None);
+        assert_eq!(compensation.contrast(19), None);
+        assert_eq!(compensation.contrast(22), Some(29));
+        // Retried until accepted, e.g. after a failed write
+        assert_eq!(compensation.contrast(22), Some(29));
+        compensation.accept(22);
+        assert_eq!(compensation.contrast(23), None);
+    }
+}
@@ -1,26 +1,69 @@
 use crate::commands::{
-    DisplayConfig, DisplaySettings, DoubleHeight, EntryModeSettings, FontWidth, HorizontalDir,
-    LineDisplayMode, OscillatorSettings, Rom, SegCommControl, VerticalDir, ViewMode,
+    Bias, DisplayConfig, DisplaySettings, DoubleHeight, EntryModeSettings, FollowerControl,
+    FontWidth, HorizontalDir, LineDisplayMode, MAX_SCROLL_DOTS, OscillatorSettings,
+    PowerIconContrast, Rom, SegCommControl, ShiftLines, VerticalDir, ViewMode,
 };
 
+/// How [`Lcd::write`](crate::ssd18030_i2c::Lcd::write) lays out text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextMode {
+    /// Characters go to consecutive DDRAM addresses, past the end of a row into hidden DDRAM.
+    /// Control characters are replaced by the fallback like other characters the ROM lacks.
+    #[default]
+    Raw,
+    /// Text continues in the next row at the end of a row, `\n` moves to the next row and `\r` to
+    /// the first column. Text past the last row is dropped.
+    StopAtEnd,
+    /// Like [`TextMode::StopAtEnd`], but text past the last row continues in the first row
+    WrapToTop,
+}
+
 /// Configuration structure holding current settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Config {
     pub display_control: DisplayConfig,
 
     pub entry_mode: EntryModeSettings,
 
+    /// Mirrors the segment and common scan of `view_mode`
     pub seg_comm: SegCommControl,
 
     pub view_mode: ViewMode,
 
+    /// Double height format, also decides the DH bit of `display_settings`
     pub double_height: Option<DoubleHeight>,
 
     pub charset: Rom,
 
+    /// ROM code written for characters the active ROM does not have
+    pub fallback: u8,
+
+    /// Line wrapping of written text, for left to right entry mode
+    pub text_mode: TextMode,
+
     pub display_settings: DisplaySettings,
 
+    /// Oscillator frequency, BS0 is taken from `bias` instead
     pub osc_freq_cmd: OscillatorSettings,
 
+    /// Booster, icon display and contrast (0-63)
+    pub power_icon_contrast: PowerIconContrast,
+
+    /// Voltage follower and its amplifier ratio
+    pub follower: FollowerControl,
+
+    pub bias: Bias,
+
+    /// Temperature coefficient TC2-TC0, `None` keeps the power-on value
+    pub temperature_coefficient: Option<u8>,
+
+    /// Lines moved by display shift and dot scroll, `None` keeps the power-on value
+    pub shift_lines: Option<ShiftLines>,
+
+    /// Dot scroll quantity (0-48) of the shifted lines. `None` selects display shift by whole
+    /// characters instead.
+    pub scroll_quantity: Option<u8>,
+
     pub bw_inversion: bool,
 
     pub font_width: FontWidth,
@@ -42,6 +85,8 @@ impl Default for Config {
             },
             view_mode: ViewMode::Top,
             charset: Rom::A,
+            fallback: b'?',
+            text_mode: TextMode::Raw,
             display_settings: DisplaySettings::new(
                 LineDisplayMode::TwoOrFourLines,
                 false,
@@ -49,7 +94,263 @@ impl Default for Config {
                 false,
             ),
             osc_freq_cmd: OscillatorSettings::default(),
+            power_icon_contrast: PowerIconContrast::default(),
+            follower: FollowerControl::default(),
+            bias: Bias::default(),
+            temperature_coefficient: None,
+            shift_lines: None,
+            scroll_quantity: None,
             double_height: None,
         }
     }
 }
+
+impl Config {
+    /// Start a validated configuration from the defaults, see [`ConfigBuilder`]
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+}
+
+/// Settings that do not work together on a DOGS164
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The NW bit is cleared, but the panel has four lines
+    FourLineDisabled,
+    /// Double height needs the N bit set for two or four lines
+    DoubleHeightLineMode,
+    /// Contrast above 63
+    ContrastOutOfRange,
+    /// Temperature coefficient above 7
+    TemperatureCoefficientOutOfRange,
+    /// Dot scroll quantity above 48
+    ScrollQuantityOutOfRange,
+    /// Both scan directions are reversed, which turns the view mode into the other one while the
+    /// DDRAM offset stays that of `view_mode`
+    ViewModeMismatch,
+}
+
+/// Builder for a [`Config`] that checks the combination of settings in [`ConfigBuilder::build`]
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self {
+            config: Config::default(),
+        }
+    }
+
+    /// DOGS164 mounted for top view, four lines
+    pub fn dogs164_top() -> Self {
+        Self::new().view_mode(ViewMode::Top)
+    }
+
+    /// DOGS164 mounted for bottom view, four lines
+    pub fn dogs164_bottom() -> Self {
+        Self::new().view_mode(ViewMode::Bottom)
+    }
+
+    /// DOGS164 top view with three lines, the first one in double height
+    pub fn dogs164_3_lines_double_height() -> Self {
+        Self::dogs164_top().double_height(Some(DoubleHeight::Lines3Top))
+    }
+
+    /// DOGS164 top view with two lines, both in double height
+    pub fn dogs164_2_lines_double_height() -> Self {
+        Self::dogs164_top().double_height(Some(DoubleHeight::Lines2))
+    }
+
+    pub fn display_control(mut self, display_control: DisplayConfig) -> Self {
+        self.config.display_control = display_control;
+        self
+    }
+
+    pub fn entry_mode(mut self, entry_mode: EntryModeSettings) -> Self {
+        self.config.entry_mode = entry_mode;
+        self
+    }
+
+    pub fn seg_comm(mut self, seg_comm: SegCommControl) -> Self {
+        self.config.seg_comm = seg_comm;
+        self
+    }
+
+    pub fn view_mode(mut self, view_mode: ViewMode) -> Self {
+        self.config.view_mode = view_mode;
+        self
+    }
+
+    pub fn double_height(mut self, double_height: Option<DoubleHeight>) -> Self {
+        self.config.double_height = double_height;
+        self
+    }
+
+    pub fn charset(mut self, charset: Rom) -> Self {
+        self.config.charset = charset;
+        self
+    }
+
+    pub fn fallback(mut self, fallback: u8) -> Self {
+        self.config.fallback = fallback;
+        self
+    }
+
+    pub fn text_mode(mut self, text_mode: TextMode) -> Self {
+        self.config.text_mode = text_mode;
+        self
+    }
+
+    /// Function set bits. DH is taken from [`ConfigBuilder::double_height`] instead.
+    pub fn display_settings(mut self, display_settings: DisplaySettings) -> Self {
+        self.config.display_settings = display_settings;
+        self
+    }
+
+    pub fn osc_freq(mut self, osc_freq: OscillatorSettings) -> Self {
+        self.config.osc_freq_cmd = osc_freq;
+        self
+    }
+
+    /// Contrast (0-63), checked in [`ConfigBuilder::build`]
+    pub fn contrast(mut self, contrast: u8) -> Self {
+        self.config.power_icon_contrast.contrast = contrast;
+        self
+    }
+
+    pub fn booster(mut self, booster: bool) -> Self {
+        self.config.power_icon_contrast.booster = booster;
+        self
+    }
+
+    pub fn follower(mut self, follower: FollowerControl) -> Self {
+        self.config.follower = follower;
+        self
+    }
+
+    pub fn bias(mut self, bias: Bias) -> Self {
+        self.config.bias = bias;
+        self
+    }
+
+    pub fn temperature_coefficient(mut self, temperature_coefficient: Option<u8>) -> Self {
+        self.config.temperature_coefficient = temperature_coefficient;
+        self
+    }
+
+    pub fn shift_lines(mut self, shift_lines: Option<ShiftLines>) -> Self {
+        self.config.shift_lines = shift_lines;
+        self
+    }
+
+    pub fn scroll_quantity(mut self, scroll_quantity: Option<u8>) -> Self {
+        self.config.scroll_quantity = scroll_quantity;
+        self
+    }
+
+    pub fn bw_inversion(mut self, bw_inversion: bool) -> Self {
+        self.config.bw_inversion = bw_inversion;
+        self
+    }
+
+    pub fn font_width(mut self, font_width: FontWidth) -> Self {
+        self.config.font_width = font_width;
+        self
+    }
+
+    pub fn four_line_enabled(mut self, four_line_enabled: bool) -> Self {
+        self.config.four_line_enabled = four_line_enabled;
+        self
+    }
+
+    /// Check the settings and return the configuration
+    pub fn build(self) -> Result<Config, ConfigError> {
+        let mut config = self.config;
+
+        if !config.four_line_enabled {
+            return Err(ConfigError::FourLineDisabled);
+        }
+
+        let line_mode = config.display_settings.line_number_control;
+        if config.double_height.is_some() && line_mode != LineDisplayMode::TwoOrFourLines {
+            return Err(ConfigError::DoubleHeightLineMode);
+        }
+
+        if config.power_icon_contrast.contrast > 63 {
+            return Err(ConfigError::ContrastOutOfRange);
+        }
+
+        if config.temperature_coefficient.is_some_and(|tc| tc > 7) {
+            return Err(ConfigError::TemperatureCoefficientOutOfRange);
+        }
+
+        if config
+            .scroll_quantity
+            .is_some_and(|dots| dots > MAX_SCROLL_DOTS)
+        {
+            return Err(ConfigError::ScrollQuantityOutOfRange);
+        }
+
+        let SegCommControl { seg_dir, com_dir } = config.seg_comm;
+        if seg_dir == HorizontalDir::RightToLeft && com_dir == VerticalDir::BottomToTop {
+            return Err(ConfigError::ViewModeMismatch);
+        }
+
+        config.display_settings.double_height = config.double_height.is_some();
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_presets() {
+        let config = ConfigBuilder::dogs164_bottom().build().unwrap();
+        assert_eq!(config.view_mode, ViewMode::Bottom);
+        assert_eq!(config.double_height, None);
+
+        let config = ConfigBuilder::dogs164_2_lines_double_height()
+            .build()
+            .unwrap();
+        assert_eq!(config.double_height, Some(DoubleHeight::Lines2));
+        assert!(config.display_settings.double_height);
+    }
+
+    #[test]
+    fn test_builder_rejects_contradictions() {
+        assert_eq!(
+            Config::builder().four_line_enabled(false).build(),
+            Err(ConfigError::FourLineDisabled)
+        );
+
+        let one_or_three =
+            DisplaySettings::new(LineDisplayMode::OneOrThreeLines, false, false, false);
+        assert_eq!(
+            ConfigBuilder::dogs164_3_lines_double_height()
+                .display_settings(one_or_three)
+                .build(),
+            Err(ConfigError::DoubleHeightLineMode)
+        );
+
+        let mirrored = SegCommControl::new(HorizontalDir::RightToLeft, VerticalDir::BottomToTop);
+        assert_eq!(
+            ConfigBuilder::dogs164_bottom().seg_comm(mirrored).build(),
+            Err(ConfigError::ViewModeMismatch)
+        );
+
+        assert_eq!(
+            Config::builder().contrast(64).build(),
+            Err(ConfigError::ContrastOutOfRange)
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Typed SSD1803A instruction set.
+//!
+//! Most instruction codes mean different things depending on the RE and IS bits of the last
+//! function set. Every [`Instruction`] knows the [`Bank`] it has to be sent in, so the driver can
+//! insert the required function sets on its own, see `SSD18030::execute`.
+use crate::commands::{
+    CMD_CLEAR_DISPLAY, CMD_DISPLAY, CMD_POWER_DOWN_DISABLE, CMD_RETURN_HOME, CMD_ROM_SELECT,
+    CMD_TEMP_COEFFICIENT, DisplayConfig, DoubleHeightBiasDisplayShift, EntryModeSettings,
+    ExtendedFunctionSet, FollowerControl, OscillatorSettings, PowerIconContrast, Rom,
+    SegCommControl, ShiftLines, ShiftSettings, ViewMode,
+};
+
+/// Function set prefix, followed by DL N DH/BE RE IS/REV
+pub const FUNCTION_SET: u8 = 0x20;
+/// Mask selecting the function set prefix
+pub const FUNCTION_SET_MASK: u8 = 0xE0;
+/// RE bit of the function set
+pub const FUNCTION_SET_RE: u8 = 0x02;
+/// IS bit of the function set, only present with RE = 0
+pub const FUNCTION_SET_IS: u8 = 0x01;
+
+/// RE and IS bits an instruction is decoded with. `None` stands for either value, or for an unknown
+/// value when tracking the state of the controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bank {
+    pub re: Option<bool>,
+
+    pub is: Option<bool>,
+}
+
+impl Bank {
+    pub const ANY: Bank = Bank::new(None, None);
+    pub const RE0: Bank = Bank::new(Some(false), None);
+    pub const RE1: Bank = Bank::new(Some(true), None);
+    pub const RE0_IS0: Bank = Bank::new(Some(false), Some(false));
+    pub const RE0_IS1: Bank = Bank::new(Some(false), Some(true));
+    pub const RE1_IS0: Bank = Bank::new(Some(true), Some(false));
+    pub const RE1_IS1: Bank = Bank::new(Some(true), Some(true));
+
+    pub const fn new(re: Option<bool>, is: Option<bool>) -> Self {
+        Self { re, is }
+    }
+
+    /// Whether an instruction tagged with `required` is decoded correctly in this bank
+    pub fn satisfies(&self, required: Bank) -> bool {
+        let matches = |current: Option<bool>, required: Option<bool>| {
+            required.is_none() || current == required
+        };
+        matches(self.re, required.re) && matches(self.is, required.is)
+    }
+
+    /// Bank after the function set `command` has been executed
+    pub fn after_function_set(&self, command: u8) -> Bank {
+        if command & FUNCTION_SET_RE != 0 {
+            // The lowest bit is REV with RE = 1, IS keeps its value
+            Bank::new(Some(true), self.is)
+        } else {
+            Bank::new(Some(false), Some(command & FUNCTION_SET_IS != 0))
+        }
+    }
+}
+
+/// SSD1803A instructions, apart from the function set which the driver derives from the
+/// display settings whenever it has to switch banks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// Clear DDRAM and return the cursor home
+    ClearDisplay,
+
+    /// Return the cursor and a shifted display home (RE = 0)
+    ReturnHome,
+
+    /// Power down mode (RE = 1)
+    PowerDown(bool),
+
+    /// Cursor direction and display shift on write (RE = 0)
+    EntryMode(EntryModeSettings),
+
+    /// Top or bottom view, with the segment and common scan mirrored as given (RE = 1)
+    ViewMode(ViewMode, SegCommControl),
+
+    /// Display, cursor and blink on/off (RE = 0)
+    DisplayControl(DisplayConfig),
+
+    /// Font width, black/white inverted cursor and 3/4 line mode (RE = 1)
+    ExtendedFunctionSet(ExtendedFunctionSet),
+
+    /// Cursor or display shift (RE = 0, IS = 0)
+    Shift(ShiftSettings),
+
+    /// Double height format, BS1 bias bit and dot scroll/shift select (RE = 1, IS = 0)
+    DoubleHeightBiasDotShift(DoubleHeightBiasDisplayShift),
+
+    /// BS0 bias bit and oscillator frequency (RE = 0, IS = 1)
+    Oscillator(OscillatorSettings),
+
+    /// Per line shift or scroll enable, lowest bit for the first line (RE = 1, IS = 1)
+    ShiftScrollEnable(ShiftLines),
+
+    /// Icon display, booster and the upper contrast bits C5-C4 (RE = 0, IS = 1)
+    PowerIconContrast(PowerIconContrast),
+
+    /// Lower contrast bits C3-C0 (RE = 0, IS = 1)
+    Contrast(PowerIconContrast),
+
+    /// Voltage follower on/off and amplifier ratio (RE = 0, IS = 1)
+    FollowerControl(FollowerControl),
+
+    /// CGRAM address AC5-AC0 (RE = 0, IS = 0)
+    SetCgramAddress(u8),
+
+    /// SEGRAM address AC3-AC0 (RE = 0, IS = 1)
+    SetSegramAddress(u8),
+
+    /// DDRAM address AC6-AC0 (RE = 0)
+    SetDdramAddress(u8),
+
+    /// Horizontal dot scroll quantity SQ5-SQ0 (RE = 1)
+    SetScrollQuantity(u8),
+
+    /// Character ROM selection, followed by a data byte (RE = 1)
+    RomSelection(Rom),
+
+    /// Temperature coefficient TC2-TC0 of the LCD drive voltage, followed by a data byte (RE = 1)
+    TemperatureCoefficient(u8),
+}
+
+impl Instruction {
+    /// Bank the instruction code has to be sent in
+    pub fn bank(&self) -> Bank {
+        match self {
+            Instruction::ClearDisplay => Bank::ANY,
+            Instruction::ReturnHome
+            | Instruction::EntryMode(_)
+            | Instruction::DisplayControl(_)
+            | Instruction::SetDdramAddress(_) => Bank::RE0,
+            Instruction::PowerDown(_)
+            | Instruction::ViewMode(..)
+            | Instruction::ExtendedFunctionSet(_)
+            | Instruction::SetScrollQuantity(_)
+            | Instruction::RomSelection(_)
+            | Instruction::TemperatureCoefficient(_) => Bank::RE1,
+            Instruction::Shift(_) | Instruction::SetCgramAddress(_) => Bank::RE0_IS0,
+            Instruction::Oscillator(_)
+            | Instruction::PowerIconContrast(_)
+            | Instruction::Contrast(_)
+            | Instruction::FollowerControl(_)
+            | Instruction::SetSegramAddress(_) => Bank::RE0_IS1,
+            Instruction::DoubleHeightBiasDotShift(_) => Bank::RE1_IS0,
+            Instruction::ShiftScrollEnable(_) => Bank::RE1_IS1,
+        }
+    }
+
+    /// Instruction code
+    pub fn cmd(&self) -> u8 {
+        match self {
+            Instruction::ClearDisplay => CMD_CLEAR_DISPLAY,
+            Instruction::ReturnHome => CMD_RETURN_HOME,
+            Instruction::PowerDown(enabled) => CMD_POWER_DOWN_DISABLE | *enabled as u8,
+            Instruction::EntryMode(settings) => settings.cmd(),
+            Instruction::ViewMode(mode, seg_comm) => seg_comm.cmd(*mode),
+            Instruction::DisplayControl(flags) => CMD_DISPLAY | flags.bits(),
+            Instruction::ExtendedFunctionSet(settings) => settings.cmd(),
+            Instruction::Shift(settings) => settings.cmd(),
+            Instruction::DoubleHeightBiasDotShift(settings) => settings.cmd(),
+            Instruction::Oscillator(settings) => settings.cmd(),
+            Instruction::ShiftScrollEnable(lines) => 0x10 | lines.bits(),
+            Instruction::PowerIconContrast(settings) => settings.cmd_byte1(),
+            Instruction::Contrast(settings) => settings.cmd_byte2(),
+            Instruction::FollowerControl(settings) => settings.cmd(),
+            Instruction::SetCgramAddress(addr) => 0x40 | (addr & 0x3F),
+            Instruction::SetSegramAddress(addr) => 0x40 | (addr & 0x0F),
+            Instruction::SetDdramAddress(addr) => 0x80 | (addr & 0x7F),
+            Instruction::SetScrollQuantity(dots) => 0x80 | (dots & 0x3F),
+            Instruction::RomSelection(_) => CMD_ROM_SELECT,
+            Instruction::TemperatureCoefficient(_) => CMD_TEMP_COEFFICIENT,
+        }
+    }
+
+    /// Data byte that has to follow the instruction code
+    pub fn parameter(&self) -> Option<u8> {
+        match self {
+            Instruction::RomSelection(rom) => Some(*rom as u8),
+            Instruction::TemperatureCoefficient(tc) => Some(tc & 0x07),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{DoubleHeight, HorizontalDir, OscillatorFreq, VerticalDir};
+
+    #[test]
+    fn test_instruction_cmd() {
+        assert_eq!(Instruction::ClearDisplay.cmd(), 0x01);
+        assert_eq!(Instruction::PowerDown(true).cmd(), 0x03);
+        let seg_comm = SegCommControl::new(HorizontalDir::LeftToRight, VerticalDir::TopToBottom);
+        assert_eq!(
+            Instruction::ViewMode(ViewMode::Bottom, seg_comm).cmd(),
+            0x06
+        );
+        assert_eq!(Instruction::SetDdramAddress(0xA4).cmd(), 0xA4);
+        assert_eq!(Instruction::SetCgramAddress(0x08).cmd(), 0x48);
+        assert_eq!(Instruction::SetScrollQuantity(0x48).cmd(), 0x88);
+        assert_eq!(
+            Instruction::ShiftScrollEnable(ShiftLines::LINE1).cmd(),
+            0x11
+        );
+
+        let entry = EntryModeSettings::new(HorizontalDir::LeftToRight, false);
+        assert_eq!(Instruction::EntryMode(entry).cmd(), 0x06);
+
+        let bias = DoubleHeightBiasDisplayShift::new(DoubleHeight::Lines3Top, false, true);
+        assert_eq!(Instruction::DoubleHeightBiasDotShift(bias).cmd(), 0x1E);
+
+        let osc = OscillatorSettings::new(OscillatorFreq::Freq540kHz, true);
+        assert_eq!(Instruction::Oscillator(osc).cmd(), 0x1B);
+
+        let rom = Instruction::RomSelection(Rom::B);
+        assert_eq!((rom.cmd(), rom.parameter()), (0x72, Some(0x04)));
+
+        let tc = Instruction::TemperatureCoefficient(0x0A);
+        assert_eq!((tc.cmd(), tc.parameter()), (0x76, Some(0x02)));
+    }
+
+    #[test]
+    fn test_bank_satisfies() {
+        assert!(Bank::RE0_IS1.satisfies(Bank::RE0));
+        assert!(Bank::RE0_IS1.satisfies(Bank::ANY));
+        assert!(!Bank::RE0_IS1.satisfies(Bank::RE0_IS0));
+        assert!(!Bank::RE1.satisfies(Bank::RE1_IS0));
+        assert!(Bank::default().satisfies(Bank::ANY));
+        assert!(!Bank::default().satisfies(Bank::RE0));
+    }
+
+    #[test]
+    fn test_bank_after_function_set() {
+        assert_eq!(Bank::ANY.after_function_set(0x39), Bank::RE0_IS1);
+        assert_eq!(Bank::RE0_IS1.after_function_set(0x3A), Bank::RE1_IS1);
+        // REV set with RE = 1 leaves IS alone
+        assert_eq!(Bank::RE0_IS0.after_function_set(0x3B), Bank::RE1_IS0);
+        assert_eq!(Bank::ANY.after_function_set(0x3A), Bank::RE1);
+    }
+}
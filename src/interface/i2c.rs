@@ -0,0 +1,111 @@
+use crate::commands::{MODE_COMMAND, MODE_CONTINUATION, MODE_DATA};
+use crate::interface::{Frame, Interface};
+use embedded_hal::i2c::{I2c, Operation};
+use heapless::Vec;
+
+/// Possible addresses, selected by the SA0 strap
+pub const I2C_ADDRESSES: [u8; 2] = [0x3C, 0x3D];
+
+/// Frames encoded into a single I2C write
+pub(crate) const I2C_BATCH_FRAMES: usize = 32;
+
+/// Encode up to [`I2C_BATCH_FRAMES`] frames behind control bytes. Every frame gets its own control
+/// byte with the Co bit set, except for the trailing run of frames going to the same register
+/// which shares one last control byte with Co cleared.
+pub(crate) fn encode_frames(frames: &[Frame]) -> Vec<u8, { 2 * I2C_BATCH_FRAMES }> {
+    let mut vec = Vec::new();
+    let Some(last) = frames.last() else {
+        return vec;
+    };
+
+    let same_register = |frame: &Frame| {
+        matches!(
+            (frame, last),
+            (Frame::Command(_), Frame::Command(_)) | (Frame::Data(_), Frame::Data(_))
+        )
+    };
+    let tail = frames
+        .iter()
+        .rposition(|frame| !same_register(frame))
+        .map_or(0, |i| i + 1);
+
+    for frame in &frames[..tail] {
+        let (control, byte) = match frame {
+            Frame::Command(command) => (MODE_CONTINUATION | MODE_COMMAND, *command),
+            Frame::Data(data) => (MODE_CONTINUATION | MODE_DATA, *data),
+        };
+        vec.push(control).unwrap();
+        vec.push(byte).unwrap();
+    }
+
+    let control = match last {
+        Frame::Command(_) => MODE_COMMAND,
+        Frame::Data(_) => MODE_DATA,
+    };
+    vec.push(control).unwrap();
+    for frame in &frames[tail..] {
+        let (Frame::Command(byte) | Frame::Data(byte)) = frame;
+        vec.push(*byte).unwrap();
+    }
+    vec
+}
+
+/// I2C transport. The controller answers on 0x3C or 0x3D depending on the SA0 strap.
+pub struct I2cInterface<B: I2c> {
+    i2c: B,
+
+    address: u8,
+}
+
+impl<B: I2c> I2cInterface<B> {
+    pub fn new(i2c: B, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    /// Give back the underlying bus
+    pub fn release(self) -> B {
+        self.i2c
+    }
+
+    /// Read the busy flag and address counter, or the part ID while RE = 1
+    pub(crate) fn read_status_byte(&mut self) -> Result<u8, B::Error> {
+        let mut status = [0u8];
+        self.i2c
+            .write_read(self.address, &[MODE_COMMAND], &mut status)?;
+        Ok(status[0])
+    }
+}
+
+impl<B: I2c> Interface for I2cInterface<B> {
+    type Error = B::Error;
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        let bytes = [MODE_COMMAND, command];
+        self.i2c.write(self.address, &bytes)
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        // Adjacent writes of a transaction go out back to back without a restart, so the data can
+        // be streamed behind the control byte without copying it
+        self.i2c.transaction(
+            self.address,
+            &mut [Operation::Write(&[MODE_DATA]), Operation::Write(data)],
+        )
+    }
+
+    fn read_status(&mut self) -> Result<Option<u8>, Self::Error> {
+        Ok(Some(self.read_status_byte()?))
+    }
+
+    fn read_data(&mut self, buffer: &mut [u8]) -> Result<bool, Self::Error> {
+        self.i2c.write_read(self.address, &[MODE_DATA], buffer)?;
+        Ok(true)
+    }
+
+    fn send_batch(&mut self, frames: &[Frame]) -> Result<(), Self::Error> {
+        for chunk in frames.chunks(I2C_BATCH_FRAMES) {
+            self.i2c.write(self.address, &encode_frames(chunk))?;
+        }
+        Ok(())
+    }
+}
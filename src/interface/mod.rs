@@ -0,0 +1,64 @@
+//! Bus transports for the SSD1803A controller.
+//!
+//! The driver only talks to the controller through the [`Interface`] trait, so the same
+//! [`Lcd`](crate::ssd18030_i2c::Lcd) implementation works regardless of how the panel is strapped.
+use crate::commands::DataLength;
+
+pub mod i2c;
+pub mod parallel;
+pub mod spi;
+
+pub use i2c::I2cInterface;
+pub use parallel::{ParallelInterface, ParallelMode};
+pub use spi::SpiInterface;
+
+/// A single byte for the instruction (RS = 0) or data (RS = 1) register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame {
+    Command(u8),
+    Data(u8),
+}
+
+/// Low level access to the instruction and data registers of the controller
+pub trait Interface {
+    type Error;
+
+    /// Write a single byte to the instruction register (RS = 0)
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error>;
+
+    /// Write bytes to the data register (RS = 1) at the current address counter
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Send a sequence of instruction and data bytes, in a single transfer where the bus allows it
+    fn send_batch(&mut self, frames: &[Frame]) -> Result<(), Self::Error> {
+        for frame in frames {
+            match frame {
+                Frame::Command(command) => self.send_command(*command)?,
+                Frame::Data(data) => self.send_data(&[*data])?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the busy flag and address counter (RS = 0, R/W = 1). Returns `None` when the bus is
+    /// wired write-only and the caller has to fall back to fixed delays.
+    fn read_status(&mut self) -> Result<Option<u8>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Read bytes from DDRAM or CGRAM at the address counter (RS = 1, R/W = 1), which increments
+    /// after each byte. Returns `false` when the bus is wired write-only.
+    fn read_data(&mut self, _buffer: &mut [u8]) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    /// Width of the data bus, reflected in the DL bit of every function set
+    fn data_length(&self) -> DataLength {
+        DataLength::EightBit
+    }
+
+    /// Bring the bus into a known state before the first instruction
+    fn init(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use crate::commands::DataLength;
+use crate::interface::Interface;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{OutputPin, PinState};
+
+/// Enable pulse width and hold time in ns, covers both bus timings at 2.7 V
+const PULSE_NS: u32 = 500;
+/// Execution time of a regular instruction in µs at the slowest oscillator setting
+const EXEC_US: u32 = 50;
+
+/// Bus timing selected by the PS/IM straps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParallelMode {
+    /// 6800 series: data latched on the falling edge of E, R/W selects the direction
+    M6800,
+    /// 8080 series: the E pin acts as /RD and the R/W pin as /WR
+    I8080,
+}
+
+/// Parallel transport driving the data lines and the E, RS and R/W pins through GPIO.
+///
+/// `N` is the bus width: 8 pins map to DB0..DB7, 4 pins map to DB4..DB7 and every byte is sent as
+/// two nibbles, high nibble first. All data pins need to share one type, most HALs provide a
+/// type-erased pin for this.
+///
+/// The data lines are only ever driven, R/W is held at write. Busy polling is therefore not
+/// available and the driver waits for the worst-case execution time instead. Calls that need to
+/// read from the controller fail, see [`Lcd`](crate::ssd18030_i2c::Lcd).
+pub struct ParallelInterface<P, E, RS, RW, DL, const N: usize> {
+    data: [P; N],
+
+    e: E,
+
+    rs: RS,
+
+    rw: RW,
+
+    mode: ParallelMode,
+
+    delay: DL,
+}
+
+impl<P, E, RS, RW, DL> ParallelInterface<P, E, RS, RW, DL, 8>
+where
+    P: OutputPin,
+    E: OutputPin<Error = P::Error>,
+    RS: OutputPin<Error = P::Error>,
+    RW: OutputPin<Error = P::Error>,
+    DL: DelayNs,
+{
+    /// Create an 8-bit transport, `data` holds DB0..DB7
+    pub fn new_8bit(data: [P; 8], e: E, rs: RS, rw: RW, mode: ParallelMode, delay: DL) -> Self {
+        Self {
+            data,
+            e,
+            rs,
+            rw,
+            mode,
+            delay,
+        }
+    }
+}
+
+impl<P, E, RS, RW, DL> ParallelInterface<P, E, RS, RW, DL, 4>
+where
+    P: OutputPin,
+    E: OutputPin<Error = P::Error>,
+    RS: OutputPin<Error = P::Error>,
+    RW: OutputPin<Error = P::Error>,
+    DL: DelayNs,
+{
+    /// Create a 4-bit transport, `data` holds DB4..DB7
+    pub fn new_4bit(data: [P; 4], e: E, rs: RS, rw: RW, mode: ParallelMode, delay: DL) -> Self {
+        Self {
+            data,
+            e,
+            rs,
+            rw,
+            mode,
+            delay,
+        }
+    }
+}
+
+impl<P, E, RS, RW, DL, const N: usize> ParallelInterface<P, E, RS, RW, DL, N>
+where
+    P: OutputPin,
+    E: OutputPin<Error = P::Error>,
+    RS: OutputPin<Error = P::Error>,
+    RW: OutputPin<Error = P::Error>,
+    DL: DelayNs,
+{
+    /// Give back the pins and the delay
+    pub fn release(self) -> ([P; N], E, RS, RW, DL) {
+        (self.data, self.e, self.rs, self.rw, self.delay)
+    }
+
+    fn set_idle(&mut self) -> Result<(), P::Error> {
+        match self.mode {
+            ParallelMode::M6800 => {
+                self.e.set_low()?;
+                self.rw.set_low()?;
+            }
+            ParallelMode::I8080 => {
+                self.e.set_high()?; // /RD
+                self.rw.set_high()?; // /WR
+            }
+        }
+        Ok(())
+    }
+
+    /// Put the lowest `N` bits of `value` on the data lines and latch them
+    fn write_bits(&mut self, value: u8) -> Result<(), P::Error> {
+        for (i, pin) in self.data.iter_mut().enumerate() {
+            pin.set_state(PinState::from(value & (1 << i) != 0))?;
+        }
+
+        match self.mode {
+            ParallelMode::M6800 => {
+                self.e.set_high()?;
+                self.delay.delay_ns(PULSE_NS);
+                self.e.set_low()?;
+            }
+            ParallelMode::I8080 => {
+                self.rw.set_low()?;
+                self.delay.delay_ns(PULSE_NS);
+                self.rw.set_high()?;
+            }
+        }
+        self.delay.delay_ns(PULSE_NS);
+        Ok(())
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), P::Error> {
+        if N == 4 {
+            self.write_bits(byte >> 4)?;
+            self.write_bits(byte & 0x0F)?;
+        } else {
+            self.write_bits(byte)?;
+        }
+
+        self.delay.delay_us(EXEC_US);
+        Ok(())
+    }
+}
+
+impl<P, E, RS, RW, DL, const N: usize> Interface for ParallelInterface<P, E, RS, RW, DL, N>
+where
+    P: OutputPin,
+    E: OutputPin<Error = P::Error>,
+    RS: OutputPin<Error = P::Error>,
+    RW: OutputPin<Error = P::Error>,
+    DL: DelayNs,
+{
+    type Error = P::Error;
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        self.rs.set_low()?;
+        self.write_byte(command)
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.rs.set_high()?;
+        for byte in data {
+            self.write_byte(*byte)?;
+        }
+        Ok(())
+    }
+
+    fn data_length(&self) -> DataLength {
+        if N == 4 {
+            DataLength::FourBit
+        } else {
+            DataLength::EightBit
+        }
+    }
+
+    fn init(&mut self) -> Result<(), Self::Error> {
+        self.set_idle()?;
+        self.rs.set_low()?;
+
+        if N == 4 {
+            // The controller wakes up in 8-bit mode, resynchronise and switch to 4-bit with
+            // single nibble function sets
+            for wait_us in [4100, 100, 100] {
+                self.write_bits(0x03)?;
+                self.delay.delay_us(wait_us);
+            }
+            self.write_bits(0x02)?;
+            self.delay.delay_us(EXEC_US);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use crate::interface::Interface;
+use embedded_hal::spi::{Operation, SpiDevice};
+
+/// Synchronisation bits that open every serial frame
+const SPI_SYNC: u8 = 0xF8;
+/// R/W bit of the start byte
+const SPI_READ: u8 = 0x04;
+/// RS bit of the start byte
+const SPI_RS_DATA: u8 = 0x02;
+/// Data bytes sent per frame, every byte takes two bytes on the wire
+const SPI_CHUNK: usize = 16;
+
+/// Serial (SPI) transport for the 3-wire/4-wire mode of the SSD1803A.
+///
+/// Every frame starts with a start byte `1 1 1 1 1 R/W RS 0`, followed by each data byte split into
+/// two transfers: `D0 D1 D2 D3 0 0 0 0` and `D4 D5 D6 D7 0 0 0 0`. The controller expects the
+/// nibbles LSB first, so they are bit reversed here and the `SpiDevice` must be configured for
+/// MSB first transfers (SPI mode 3 on the DOGS164).
+///
+/// Reading needs the SOD line wired to MISO (4-wire mode). In 3-wire mode the reads return garbage,
+/// so use [`SpiInterface::new_write_only`], which makes the driver wait fixed delays instead.
+pub struct SpiInterface<S: SpiDevice> {
+    spi: S,
+
+    /// SOD is wired, reads return the controller's answer
+    readable: bool,
+}
+
+impl<S: SpiDevice> SpiInterface<S> {
+    /// Transport for the 4-wire mode, reading through SOD
+    pub fn new(spi: S) -> Self {
+        Self {
+            spi,
+            readable: true,
+        }
+    }
+
+    /// Transport for the 3-wire mode without SOD, which never reads
+    pub fn new_write_only(spi: S) -> Self {
+        Self {
+            spi,
+            readable: false,
+        }
+    }
+
+    /// Give back the underlying device
+    pub fn release(self) -> S {
+        self.spi
+    }
+
+    fn start_byte(read: bool, data: bool) -> u8 {
+        let mut byte = SPI_SYNC;
+
+        if read {
+            byte |= SPI_READ;
+        }
+
+        if data {
+            byte |= SPI_RS_DATA;
+        }
+
+        byte
+    }
+
+    /// Split a byte into the two LSB first nibble transfers
+    pub(crate) fn split(byte: u8) -> [u8; 2] {
+        [(byte & 0x0F).reverse_bits(), (byte >> 4).reverse_bits()]
+    }
+
+    fn write_frame(&mut self, data: bool, bytes: &[u8]) -> Result<(), S::Error> {
+        let mut frame = [0u8; 1 + 2 * SPI_CHUNK];
+        frame[0] = Self::start_byte(false, data);
+
+        for (i, byte) in bytes.iter().enumerate() {
+            let [low, high] = Self::split(*byte);
+            frame[1 + 2 * i] = low;
+            frame[2 + 2 * i] = high;
+        }
+
+        self.spi.write(&frame[..1 + 2 * bytes.len()])
+    }
+}
+
+impl<S: SpiDevice> Interface for SpiInterface<S> {
+    type Error = S::Error;
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        self.write_frame(false, &[command])
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        for chunk in data.chunks(SPI_CHUNK) {
+            self.write_frame(true, chunk)?;
+        }
+        Ok(())
+    }
+
+    fn read_status(&mut self) -> Result<Option<u8>, Self::Error> {
+        if !self.readable {
+            return Ok(None);
+        }
+        let mut status = [0u8];
+        self.spi.transaction(&mut [
+            Operation::Write(&[Self::start_byte(true, false)]),
+            Operation::Read(&mut status),
+        ])?;
+        // Shifted out LSB first
+        Ok(Some(status[0].reverse_bits()))
+    }
+
+    fn read_data(&mut self, buffer: &mut [u8]) -> Result<bool, Self::Error> {
+        if !self.readable {
+            return Ok(false);
+        }
+        self.spi.transaction(&mut [
+            Operation::Write(&[Self::start_byte(true, true)]),
+            Operation::Read(buffer),
+        ])?;
+        for byte in buffer.iter_mut() {
+            *byte = byte.reverse_bits();
+        }
+        Ok(true)
+    }
+}
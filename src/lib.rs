@@ -1,5 +1,17 @@
 #![no_std]
+pub mod cgram;
+pub mod charset;
 pub mod commands;
+pub mod compensation;
 pub mod config;
+pub mod instruction;
+pub mod interface;
+pub mod marquee;
+pub mod segram;
+#[cfg(feature = "async")]
+pub mod ssd18030_async;
 pub mod ssd18030_i2c;
+mod state;
+#[cfg(test)]
 mod tests;
+mod text;
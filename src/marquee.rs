@@ -0,0 +1,352 @@
+//! Scrolling text for strings longer than a line.
+//!
+//! A [`Marquee`] moves its text by one character per [`Marquee::tick`]. Text that fits into the 20
+//! characters of a DDRAM line is written once and moved with the display shift of its line, so a
+//! tick costs a single instruction. Longer text is redrawn from software on every step.
+//!
+//! The display shift moves every line enabled for shifting, so only one hardware marquee should
+//! run at a time. [`Lcd::home`](crate::ssd18030_i2c::Lcd::home) and `clear` undo the shift.
+use crate::commands::{HorizontalDir, ShiftLines};
+use crate::config::Config;
+use crate::instruction::Instruction;
+use crate::interface::Interface;
+use crate::ssd18030_i2c::{
+    COLUMNS, DDRAM_LINE_LEN, Lcd, LcdError, SSD18030, ddram_offset, row_addresses,
+};
+use embedded_hal::delay::DelayNs;
+use heapless::String;
+
+/// Bytes for a DDRAM line of characters of up to 4 bytes each
+const LINE_BYTES: usize = DDRAM_LINE_LEN as usize * 4;
+
+/// How the text moves once its end is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarqueeMode {
+    /// Keep moving left, starting over with the beginning after a gap
+    Wrap,
+    /// Move back to the right until the beginning is visible again
+    Bounce,
+}
+
+/// What a tick does to the display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Shift(HorizontalDir),
+    Draw,
+}
+
+/// Text scrolling through one row, see the [module](self) documentation
+#[derive(Debug, Clone)]
+pub struct Marquee<'a> {
+    row: u8,
+
+    text: &'a str,
+
+    /// Characters in `text`
+    len: usize,
+
+    mode: MarqueeMode,
+
+    /// Ticks spent at the beginning and at the end of the text
+    pauses: (u16, u16),
+
+    /// Spaces between the end and the next beginning in wrap mode, when drawn from software
+    gap: usize,
+
+    /// Index of the first visible character
+    position: usize,
+
+    /// Moving towards the end of the text
+    forward: bool,
+
+    /// Ticks left before the next step
+    hold: u16,
+}
+
+impl<'a> Marquee<'a> {
+    /// Marquee for `text` in a 1-based row, wrapping without pauses
+    pub fn new(row: u8, text: &'a str) -> Self {
+        Self {
+            row,
+            text,
+            len: text.chars().count(),
+            mode: MarqueeMode::Wrap,
+            pauses: (0, 0),
+            gap: 4,
+            position: 0,
+            forward: true,
+            hold: 0,
+        }
+    }
+
+    pub fn mode(mut self, mode: MarqueeMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Ticks to stay with the beginning and with the end of the text in view
+    pub fn pauses(mut self, start: u16, end: u16) -> Self {
+        self.pauses = (start, end);
+        self
+    }
+
+    /// Spaces between the end and the next beginning of text drawn from software in wrap mode.
+    /// Text moved by the display shift is padded to the DDRAM line instead.
+    pub fn gap(mut self, gap: usize) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// The text fits without moving
+    fn fits(&self) -> bool {
+        self.len <= COLUMNS as usize
+    }
+
+    /// The text is moved with the display shift instead of being redrawn
+    fn hardware(&self) -> bool {
+        !self.fits() && self.len <= DDRAM_LINE_LEN as usize
+    }
+
+    /// Position at which the end of the text reaches the last column
+    fn end(&self) -> usize {
+        self.len - COLUMNS as usize
+    }
+
+    /// Positions before wrap mode starts over
+    fn period(&self) -> usize {
+        if self.hardware() {
+            DDRAM_LINE_LEN as usize
+        } else {
+            self.len + self.gap
+        }
+    }
+
+    fn reset(&mut self) {
+        self.position = 0;
+        self.forward = true;
+        self.hold = self.pauses.0;
+    }
+
+    /// Move on by one tick, `None` while pausing or when the text fits
+    fn advance(&mut self) -> Option<Step> {
+        if self.fits() {
+            return None;
+        }
+        if self.hold > 0 {
+            self.hold -= 1;
+            return None;
+        }
+
+        let direction = match self.mode {
+            MarqueeMode::Wrap => {
+                self.position = (self.position + 1) % self.period();
+                HorizontalDir::RightToLeft
+            }
+            MarqueeMode::Bounce if self.forward => {
+                self.position += 1;
+                self.forward = self.position < self.end();
+                HorizontalDir::RightToLeft
+            }
+            MarqueeMode::Bounce => {
+                self.position -= 1;
+                self.forward = self.position == 0;
+                HorizontalDir::LeftToRight
+            }
+        };
+
+        if self.position == 0 {
+            self.hold = self.pauses.0;
+        } else if self.position == self.end() {
+            self.hold = self.pauses.1;
+        }
+
+        if self.hardware() {
+            Some(Step::Shift(direction))
+        } else {
+            Some(Step::Draw)
+        }
+    }
+
+    /// Visible columns for the current position
+    fn window(&self) -> String<LINE_BYTES> {
+        let mut window = String::new();
+        let gap = core::iter::repeat_n(' ', self.gap);
+        let visible = self.text.chars().chain(gap).cycle().skip(self.position);
+        for c in visible.take(COLUMNS as usize) {
+            window.push(c).unwrap();
+        }
+        window
+    }
+
+    /// Text padded to a whole DDRAM line, split where the line wraps around behind the first
+    /// visible column `offset`
+    fn line(&self, offset: u8) -> (String<LINE_BYTES>, String<LINE_BYTES>) {
+        let padded = self.text.chars().chain(core::iter::repeat(' '));
+        let mut parts = (String::new(), String::new());
+        for (i, c) in padded.take(DDRAM_LINE_LEN as usize).enumerate() {
+            let part = if i < (DDRAM_LINE_LEN - offset) as usize {
+                &mut parts.0
+            } else {
+                &mut parts.1
+            };
+            part.push(c).unwrap();
+        }
+        parts
+    }
+
+    /// DDRAM line shown in the row, `None` if the layout of `config` has no such row
+    fn line_address(&self, config: &Config) -> Option<u8> {
+        let rows = row_addresses(config.double_height);
+        rows.get(self.row.checked_sub(1)? as usize).copied()
+    }
+
+    /// Show the beginning of the text. The lines must not be shifted yet.
+    pub fn start<IF: Interface, D: DelayNs, RST>(
+        &mut self,
+        lcd: &mut SSD18030<IF, D, RST>,
+    ) -> Result<(), LcdError<IF::Error>> {
+        let address = self
+            .line_address(lcd.config())
+            .ok_or(LcdError::InvalidInputData)?;
+
+        self.reset();
+        if !self.hardware() {
+            return self.draw(lcd);
+        }
+
+        let line = ShiftLines::from_bits_truncate(1 << (address >> 5));
+        let lines = lcd.config().shift_lines.map_or(line, |lines| lines | line);
+        lcd.enable_line_shift(lines)?;
+
+        let offset = ddram_offset(lcd.config().view_mode);
+        let (visible, behind) = self.line(offset);
+        lcd.execute(Instruction::SetDdramAddress(address + offset))?;
+        lcd.write_run(&visible)?;
+        lcd.execute(Instruction::SetDdramAddress(address))?;
+        lcd.write_run(&behind)
+    }
+
+    /// Advance by one step
+    pub fn tick<IF: Interface, D: DelayNs, RST>(
+        &mut self,
+        lcd: &mut SSD18030<IF, D, RST>,
+    ) -> Result<(), LcdError<IF::Error>> {
+        match self.advance() {
+            None => Ok(()),
+            Some(Step::Shift(direction)) => lcd.shift_display(direction),
+            Some(Step::Draw) => self.draw(lcd),
+        }
+    }
+
+    fn draw<IF: Interface, D: DelayNs, RST>(
+        &self,
+        lcd: &mut SSD18030<IF, D, RST>,
+    ) -> Result<(), LcdError<IF::Error>> {
+        lcd.locate(self.row, 1)?;
+        lcd.write(&self.window())
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use super::{Marquee, Step};
+    use crate::commands::ShiftLines;
+    use crate::instruction::Instruction;
+    use crate::ssd18030_async::{LcdAsync, SSD18030Async};
+    use crate::ssd18030_i2c::{LcdError, ddram_offset};
+    use embedded_hal_async::delay::DelayNs;
+    use embedded_hal_async::i2c::I2c;
+
+    impl Marquee<'_> {
+        /// Show the beginning of the text. The lines must not be shifted yet.
+        pub async fn start_async<B: I2c, D: DelayNs, RST>(
+            &mut self,
+            lcd: &mut SSD18030Async<B, D, RST>,
+        ) -> Result<(), LcdError<B::Error>> {
+            let address = self
+                .line_address(lcd.config())
+                .ok_or(LcdError::InvalidInputData)?;
+
+            self.reset();
+            if !self.hardware() {
+                return self.draw_async(lcd).await;
+            }
+
+            let line = ShiftLines::from_bits_truncate(1 << (address >> 5));
+            let lines = lcd.config().shift_lines.map_or(line, |lines| lines | line);
+            lcd.enable_line_shift(lines).await?;
+
+            let offset = ddram_offset(lcd.config().view_mode);
+            let (visible, behind) = self.line(offset);
+            lcd.execute(Instruction::SetDdramAddress(address + offset))
+                .await?;
+            lcd.write_run(&visible).await?;
+            lcd.execute(Instruction::SetDdramAddress(address)).await?;
+            lcd.write_run(&behind).await
+        }
+
+        /// Advance by one step
+        pub async fn tick_async<B: I2c, D: DelayNs, RST>(
+            &mut self,
+            lcd: &mut SSD18030Async<B, D, RST>,
+        ) -> Result<(), LcdError<B::Error>> {
+            match self.advance() {
+                None => Ok(()),
+                Some(Step::Shift(direction)) => lcd.shift_display(direction).await,
+                Some(Step::Draw) => self.draw_async(lcd).await,
+            }
+        }
+
+        async fn draw_async<B: I2c, D: DelayNs, RST>(
+            &self,
+            lcd: &mut SSD18030Async<B, D, RST>,
+        ) -> Result<(), LcdError<B::Error>> {
+            lcd.locate(self.row, 1).await?;
+            lcd.write(&self.window()).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounce_with_pauses() {
+        let mut marquee = Marquee::new(1, "0123456789abcdefgh")
+            .mode(MarqueeMode::Bounce)
+            .pauses(1, 2);
+        marquee.reset();
+
+        let steps: [_; 9] = core::array::from_fn(|_| marquee.advance());
+        let left = Some(Step::Shift(HorizontalDir::RightToLeft));
+        let right = Some(Step::Shift(HorizontalDir::LeftToRight));
+        assert_eq!(
+            steps,
+            [None, left, left, None, None, right, right, None, left]
+        );
+    }
+
+    #[test]
+    fn test_wrap_from_software() {
+        let mut marquee = Marquee::new(2, "The quick brown fox jumps").gap(2);
+        marquee.reset();
+        assert_eq!(marquee.window(), "The quick brown ");
+
+        for _ in 0..20 {
+            assert_eq!(marquee.advance(), Some(Step::Draw));
+        }
+        assert_eq!(marquee.window(), "jumps  The quick");
+        for _ in 0..7 {
+            marquee.advance();
+        }
+        assert_eq!(marquee.position, 0);
+    }
+
+    #[test]
+    fn test_short_text_stays() {
+        let mut marquee = Marquee::new(1, "fits");
+        marquee.reset();
+        assert_eq!(marquee.advance(), None);
+    }
+}
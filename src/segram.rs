@@ -0,0 +1,94 @@
+//! Icons in segment RAM.
+//!
+//! SEGRAM holds 16 bytes with 5 icon segments each, S1 in bit 4 down to S5 in bit 0, so icon `n`
+//! is bit `4 - n % 5` at address `n / 5`. Which segment of the glass an index drives depends on
+//! the panel. The icons are shown while icon display is enabled, see
+//! [`SSD18030::set_icons_enabled`](crate::ssd18030_i2c::SSD18030::set_icons_enabled).
+
+/// Bytes of segment RAM
+pub const SEGRAM_SIZE: usize = 16;
+
+/// Number of icons the controller drives
+pub const ICONS: u8 = SEGRAM_SIZE as u8 * ICONS_PER_ADDRESS;
+
+const ICONS_PER_ADDRESS: u8 = 5;
+
+/// Bit B1 of a SEGRAM byte, which makes its icons blink while data blink (BE) is enabled
+pub const ICON_BLINK: u8 = 0x80;
+
+/// Copy of the SEGRAM contents, kept by the driver since the controller cannot read them back
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Icons {
+    data: [u8; SEGRAM_SIZE],
+}
+
+impl Icons {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// SEGRAM address and segment bit of an icon, `None` if there is no such icon
+    fn position(index: u8) -> Option<(u8, u8)> {
+        (index < ICONS).then(|| {
+            let bit = ICONS_PER_ADDRESS - 1 - index % ICONS_PER_ADDRESS;
+            (index / ICONS_PER_ADDRESS, 1 << bit)
+        })
+    }
+
+    /// Switch an icon on or off, returns its SEGRAM address and the new byte there
+    pub fn set(&mut self, index: u8, on: bool) -> Option<(u8, u8)> {
+        let (address, bit) = Self::position(index)?;
+        self.update(address, bit, on)
+    }
+
+    /// Let an icon blink, returns its SEGRAM address and the new byte there. The controller
+    /// blinks all five icons sharing the address.
+    pub fn set_blink(&mut self, index: u8, blink: bool) -> Option<(u8, u8)> {
+        let (address, _) = Self::position(index)?;
+        self.update(address, ICON_BLINK, blink)
+    }
+
+    fn update(&mut self, address: u8, bits: u8, set: bool) -> Option<(u8, u8)> {
+        let byte = &mut self.data[address as usize];
+        if set {
+            *byte |= bits;
+        } else {
+            *byte &= !bits;
+        }
+        Some((address, *byte))
+    }
+
+    /// The icon is switched on
+    pub fn is_on(&self, index: u8) -> bool {
+        Self::position(index).is_some_and(|(address, bit)| self.data[address as usize] & bit != 0)
+    }
+
+    /// Replace the whole contents
+    pub fn load(&mut self, data: &[u8; SEGRAM_SIZE]) {
+        self.data = *data;
+    }
+
+    pub fn data(&self) -> &[u8; SEGRAM_SIZE] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icon_positions() {
+        let mut icons = Icons::new();
+        assert_eq!(icons.set(0, true), Some((0, 0x10)));
+        assert_eq!(icons.set(4, true), Some((0, 0x11)));
+        assert_eq!(icons.set(79, true), Some((15, 0x01)));
+        assert_eq!(icons.set(80, true), None);
+        assert!(icons.is_on(4));
+        assert!(!icons.is_on(3));
+
+        assert_eq!(icons.set_blink(2, true), Some((0, 0x91)));
+        assert_eq!(icons.set(0, false), Some((0, 0x81)));
+        assert_eq!(icons.set_blink(3, false), Some((0, 0x01)));
+    }
+}
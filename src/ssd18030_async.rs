@@ -0,0 +1,819 @@
+//! Async variant of the [`SSD18030`](crate::ssd18030_i2c::SSD18030) driver for executors such as
+//! Embassy. Both drivers build the same frames from the same state, only the bus accesses and
+//! delays are awaited.
+use crate::cgram::{Glyph, GlyphCache, GlyphHandle, GlyphSlot, StaleCells};
+use crate::charset;
+use crate::commands::{
+    Bias, DataLength, DisplayConfig, DoubleHeight, EntryModeSettings, FollowerControl,
+    HorizontalDir, MODE_COMMAND, MODE_DATA, Rom, SSD1803A_PART_ID, ShiftLines, ShiftSettings,
+    ShiftType, Status, ViewMode,
+};
+use crate::compensation::TemperatureCompensation;
+use crate::config::{Config, TextMode};
+use crate::instruction::{Bank, Instruction};
+use crate::interface::i2c::{I2C_ADDRESSES, I2C_BATCH_FRAMES, encode_frames};
+use crate::segram::{Icons, SEGRAM_SIZE};
+use crate::ssd18030_i2c::{
+    BUSY_POLL_US, COLUMNS, LcdError, NoResetPin, POWER_UP_MS, ProbeError, RESET_LOW_US,
+    RESET_RECOVERY_MS, SLOW_CMD_TIMEOUT_US, WRITE_CHUNK, ddram_position,
+};
+use crate::state::{AddressCounter, MAX_INSTRUCTION_FRAMES, QUEUE_LEN, State};
+use crate::text::{TextRuns, TextStep};
+use embedded_hal::digital::OutputPin;
+use embedded_hal::i2c::{Error as _, ErrorKind};
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::{I2c, Operation};
+use heapless::Vec;
+
+/// Async counterpart of the [`Lcd`](crate::ssd18030_i2c::Lcd) trait
+#[allow(async_fn_in_trait)]
+pub trait LcdAsync {
+    type Error;
+
+    async fn init(&mut self, config: Config) -> Result<(), Self::Error>;
+
+    /// Clear display and set cursor to home position
+    async fn clear(&mut self) -> Result<(), Self::Error>;
+
+    /// Set cursor to home position
+    async fn home(&mut self) -> Result<(), Self::Error>;
+
+    /// Set cursor position. Row and column are 1-based, rows count the lines of the double height
+    /// layout.
+    async fn locate(&mut self, row: u8, col: u8) -> Result<(), Self::Error>;
+
+    /// Write a string to the display at the current cursor position, laid out as selected by
+    /// [`Config::text_mode`]. Characters missing from the active ROM are replaced by the fallback
+    /// of the configuration.
+    async fn write(&mut self, s: &str) -> Result<(), Self::Error>;
+
+    /// Write ROM codes to the display at the current cursor position
+    async fn write_raw(&mut self, codes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Switch the character ROM used by the display and by [`LcdAsync::write`]
+    async fn set_rom(&mut self, rom: Rom) -> Result<(), Self::Error>;
+
+    /// Blank the display, switch off the booster and the voltage follower and enter power-down
+    /// mode. The configuration is left as it is for [`LcdAsync::wake`].
+    async fn sleep(&mut self) -> Result<(), Self::Error>;
+
+    /// Leave power-down mode and bring back the display, contrast, booster and follower of the
+    /// configuration
+    async fn wake(&mut self) -> Result<(), Self::Error>;
+
+    /// Set display control (display on/off, cursor on/off, blink on/off)
+    async fn set_display(&mut self, flags: DisplayConfig) -> Result<(), Self::Error>;
+
+    /// Shift the lines enabled for shifting by one character, the cursor moves along
+    async fn shift_display(&mut self, direction: HorizontalDir) -> Result<(), Self::Error>;
+
+    /// Move the cursor by one character without writing
+    async fn shift_cursor(&mut self, direction: HorizontalDir) -> Result<(), Self::Error>;
+
+    /// Set entry mode (set cursor/blink direction and enables shift for shift-enabled lines)
+    async fn set_entry_mode(&mut self, mode: EntryModeSettings) -> Result<(), Self::Error>;
+
+    /// Set view mode (top or bottom)
+    async fn set_view_mode(&mut self, mode: ViewMode) -> Result<(), Self::Error>;
+
+    async fn set_cursor_off(&mut self) -> Result<(), Self::Error>;
+
+    async fn set_blinking_off(&mut self) -> Result<(), Self::Error>;
+
+    /// Show or hide the underline cursor and the blinking block at the cursor position
+    async fn set_cursor(&mut self, visible: bool, blinking: bool) -> Result<(), Self::Error>;
+
+    /// Blink the cursor character in reverse video instead of as a black block
+    async fn set_cursor_inverted(&mut self, inverted: bool) -> Result<(), Self::Error>;
+
+    /// 1-based row and column of the cursor, from the tracked writes or else from the address
+    /// counter. `None` while the cursor is on a hidden DDRAM address or the address counter
+    /// points to CGRAM or SEGRAM.
+    async fn cursor(&mut self) -> Result<Option<(u8, u8)>, Self::Error>;
+
+    async fn extended_function_set(&mut self) -> Result<(), Self::Error>;
+
+    /// Switch to a double height layout, or back to four lines with `None`
+    async fn set_double_height(&mut self, mode: Option<DoubleHeight>) -> Result<(), Self::Error>;
+
+    async fn clear_line(&mut self, line: u8) -> Result<(), Self::Error>;
+
+    async fn clear_chars(&mut self, row_col: (u8, u8), chars: u8) -> Result<(), Self::Error>;
+
+    /// Write the glyph of a handle from [`SSD18030Async::alloc_glyph`] at the current cursor position
+    async fn write_special_char(&mut self, glyph: &GlyphHandle) -> Result<(), Self::Error>;
+
+    /// Write a bitmap to CGRAM `location` (0-7), bypassing the slots handed out by
+    /// [`SSD18030Async::alloc_glyph`]
+    async fn create_custom_char(&mut self, location: u8, charmap: &[u8])
+    -> Result<(), Self::Error>;
+}
+
+pub struct SSD18030Async<B: I2c, D: DelayNs, RST = NoResetPin> {
+    i2c: B,
+
+    delay: D,
+
+    address: u8,
+
+    /// /RES pin, active low
+    reset: RST,
+
+    /// Configuration, queued frames, register bank, address counter and RAM copies, shared with
+    /// the blocking driver
+    state: State,
+}
+
+impl<B: I2c, D: DelayNs> SSD18030Async<B, D> {
+    pub fn new_i2c(i2c: B, address: u8, delay: D) -> Self {
+        SSD18030Async {
+            i2c,
+            delay,
+            address,
+            reset: NoResetPin,
+            state: State::new(DataLength::EightBit),
+        }
+    }
+
+    /// Look for an SSD1803A on both SA0 addresses and return a driver for the first one whose part
+    /// ID matches. The bus is consumed, pass `&mut i2c` to keep using it after a failed probe.
+    pub async fn probe_i2c(mut i2c: B, mut delay: D) -> Result<Self, ProbeError<B::Error>> {
+        let mut wrong_chip = None;
+
+        for address in I2C_ADDRESSES {
+            let mut lcd = Self::new_i2c(i2c, address, delay);
+            match lcd.read_i2c_part_id().await {
+                Ok(SSD1803A_PART_ID) => return Ok(lcd),
+                Ok(part_id) => wrong_chip = Some(ProbeError::WrongChip { address, part_id }),
+                Err(err) if matches!(err.kind(), ErrorKind::NoAcknowledge(_)) => {}
+                Err(err) => return Err(ProbeError::Bus(err)),
+            }
+
+            (i2c, delay, _) = lcd.release();
+        }
+
+        Err(wrong_chip.unwrap_or(ProbeError::NotFound))
+    }
+
+    /// Attach the GPIO driving the /RES pin, which enables [`SSD18030Async::hard_reset`]
+    pub fn with_reset_pin<RST: OutputPin>(self, reset: RST) -> SSD18030Async<B, D, RST> {
+        SSD18030Async {
+            i2c: self.i2c,
+            delay: self.delay,
+            address: self.address,
+            reset,
+            state: self.state,
+        }
+    }
+}
+
+impl<B: I2c, D: DelayNs, RST: OutputPin> SSD18030Async<B, D, RST> {
+    /// Pulse /RES low and wait for the controller to come back up. All registers return to their
+    /// power-on values, so [`LcdAsync::init`] has to run again afterwards. It also brings back the
+    /// glyphs held by handles and the icons.
+    pub async fn hard_reset(&mut self) -> Result<(), RST::Error> {
+        self.state.reset();
+        self.reset.set_low()?;
+        self.delay.delay_us(RESET_LOW_US).await;
+        self.reset.set_high()?;
+        self.delay.delay_ms(RESET_RECOVERY_MS).await;
+        Ok(())
+    }
+}
+
+impl<B: I2c, D: DelayNs, RST> SSD18030Async<B, D, RST> {
+    /// Give back the bus, the delay and the reset pin
+    pub fn release(self) -> (B, D, RST) {
+        (self.i2c, self.delay, self.reset)
+    }
+
+    /// Configuration passed to [`LcdAsync::init`], along with the changes made since
+    pub fn config(&self) -> &Config {
+        &self.state.config
+    }
+
+    pub async fn send_command(&mut self, command: u8) -> Result<(), B::Error> {
+        self.state.queue_raw_command(command);
+        self.flush().await
+    }
+
+    /// Send an instruction, preceded by the function sets needed to reach its RE/IS bank. Clear
+    /// display and return home wait for the controller to finish.
+    pub async fn execute(&mut self, instruction: Instruction) -> Result<(), LcdError<B::Error>> {
+        self.state.queue_instruction(instruction);
+        match instruction {
+            Instruction::ClearDisplay | Instruction::ReturnHome => {
+                self.wait_ready(SLOW_CMD_TIMEOUT_US).await?
+            }
+            _ => self.flush().await?,
+        }
+        Ok(())
+    }
+
+    pub async fn send_data_byte(&mut self, data: u8) -> Result<(), B::Error> {
+        self.state.untracked_data();
+        self.flush().await?;
+        self.write_data(&[data]).await
+    }
+
+    pub async fn send_data(&mut self, data: &[u8]) -> Result<(), LcdError<B::Error>> {
+        self.state.untracked_data();
+        self.flush().await?;
+        self.write_data(data).await?;
+        Ok(())
+    }
+
+    /// Row and column text in a wrapping [`TextMode`] continues at
+    async fn text_start(&mut self) -> Result<(u8, u8), LcdError<B::Error>> {
+        let address = match self.state.address_counter {
+            AddressCounter::Ddram(address) => Some(address),
+            AddressCounter::Cgram | AddressCounter::Segram => None,
+            AddressCounter::Unknown => Some(self.read_status().await?.address),
+        };
+        Ok(self.state.text_start(address))
+    }
+
+    /// Encode and write characters at the address counter, regardless of the text mode
+    pub(crate) async fn write_run(&mut self, s: &str) -> Result<(), LcdError<B::Error>> {
+        let mut codes: Vec<u8, WRITE_CHUNK> = Vec::new();
+        for c in s.chars() {
+            if codes.is_full() {
+                self.write_codes(&codes).await?;
+                codes.clear();
+            }
+            let code = match charset::encode(self.state.config.charset, c) {
+                Some(code) => code,
+                None => self.fallback_code(c, &mut codes).await?,
+            };
+            codes.push(code).unwrap();
+        }
+
+        if !codes.is_empty() {
+            self.write_codes(&codes).await?;
+        }
+        Ok(())
+    }
+
+    /// Write character codes to DDRAM at the address counter
+    async fn write_codes(&mut self, codes: &[u8]) -> Result<(), LcdError<B::Error>> {
+        self.flush().await?;
+        self.write_data(codes).await?;
+        self.state.track_ddram_write(codes);
+        Ok(())
+    }
+
+    /// Code for a character missing from the ROM. A glyph that is not loaded yet is uploaded
+    /// after the codes in `pending` went out, since the upload moves the address counter.
+    async fn fallback_code(
+        &mut self,
+        c: char,
+        pending: &mut Vec<u8, WRITE_CHUNK>,
+    ) -> Result<u8, LcdError<B::Error>> {
+        match self.state.fallback_slot(c) {
+            None => Ok(self.state.config.fallback),
+            Some(GlyphSlot::Loaded(slot)) => Ok(slot),
+            Some(GlyphSlot::Upload(slot, glyph)) => {
+                if !pending.is_empty() {
+                    self.write_codes(pending).await?;
+                    pending.clear();
+                }
+                self.state.queue_glyph_upload(slot, glyph);
+                Ok(slot)
+            }
+        }
+    }
+
+    /// Substitute characters missing from the ROM with the glyphs of `glyphs` in
+    /// [`LcdAsync::write`]. The glyphs are loaded into CGRAM as they are needed, replacing the
+    /// least recently used one once all 8 slots are taken, so custom characters created before are
+    /// overwritten.
+    pub fn enable_fallback_glyphs(&mut self, glyphs: &'static [(char, Glyph)]) {
+        self.state.glyph_cache = Some(GlyphCache::new(glyphs));
+    }
+
+    /// Take a CGRAM slot showing `glyph`. A slot that already shows the same bitmap is shared
+    /// instead of taking another one, slots used for fallback glyphs are taken over when no other
+    /// slot is free. After an upload the address counter is pointed back to the DDRAM address it
+    /// had, unless the driver lost track of it after raw commands or data.
+    pub async fn alloc_glyph(&mut self, glyph: &Glyph) -> Result<GlyphHandle, LcdError<B::Error>> {
+        let handle = self.state.alloc_glyph(glyph).ok_or(LcdError::CgramFull)?;
+
+        if let Err(err) = self.send().await {
+            self.state.cgram.release(handle);
+            return Err(err);
+        }
+        Ok(handle)
+    }
+
+    /// Give back a handle from [`SSD18030Async::alloc_glyph`]. Its slot is free for other glyphs once
+    /// all handles to it are released, cells still showing it change along with the slot.
+    pub fn release_glyph(&mut self, glyph: GlyphHandle) {
+        self.state.cgram.release(glyph);
+    }
+
+    /// Go back to the fallback character of the configuration for missing characters
+    pub fn disable_fallback_glyphs(&mut self) {
+        self.state.glyph_cache = None;
+    }
+
+    /// Cells (row, column) still showing a fallback glyph whose slot was since given to another
+    /// character, so they now show that one. Each cell is reported once, the application rewrites
+    /// them as needed.
+    pub fn take_stale_cells(&mut self) -> StaleCells {
+        self.state.take_stale_cells()
+    }
+
+    /// Select how [`LcdAsync::write`] lays out text
+    pub fn set_text_mode(&mut self, text_mode: TextMode) {
+        self.state.config.text_mode = text_mode;
+    }
+
+    /// Show or hide the icons in SEGRAM
+    pub async fn set_icons_enabled(&mut self, enabled: bool) -> Result<(), LcdError<B::Error>> {
+        self.state.set_icons_enabled(enabled);
+        self.send().await
+    }
+
+    /// SEGRAM contents as written by the driver
+    pub fn icons(&self) -> &Icons {
+        &self.state.icons
+    }
+
+    /// Switch an icon on or off, see [`segram`](crate::segram) for the numbering
+    pub async fn set_icon(&mut self, index: u8, on: bool) -> Result<(), LcdError<B::Error>> {
+        self.state
+            .set_icon(index, on)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send().await
+    }
+
+    /// Let an icon blink while data blink is enabled. All five icons sharing its SEGRAM address
+    /// blink together.
+    pub async fn set_icon_blink(
+        &mut self,
+        index: u8,
+        blink: bool,
+    ) -> Result<(), LcdError<B::Error>> {
+        self.state
+            .set_icon_blink(index, blink)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send().await
+    }
+
+    /// Replace the whole SEGRAM
+    pub async fn write_icons(
+        &mut self,
+        data: &[u8; SEGRAM_SIZE],
+    ) -> Result<(), LcdError<B::Error>> {
+        self.state.write_icons(data);
+        self.send().await
+    }
+
+    /// Read the busy flag and address counter
+    pub async fn read_status(&mut self) -> Result<Status, LcdError<B::Error>> {
+        self.state.select_bank(Bank::RE0);
+        self.flush().await?;
+        Ok(Status::from(self.read_status_byte().await?))
+    }
+
+    /// Read the part ID, which the controller reports instead of the address counter while RE = 1
+    pub async fn read_part_id(&mut self) -> Result<u8, LcdError<B::Error>> {
+        Ok(self.read_i2c_part_id().await?)
+    }
+
+    async fn read_i2c_part_id(&mut self) -> Result<u8, B::Error> {
+        self.state.select_bank(Bank::RE1);
+        self.flush().await?;
+        Ok(Status::from(self.read_status_byte().await?).address)
+    }
+
+    async fn read_status_byte(&mut self) -> Result<u8, B::Error> {
+        let mut status = [0u8];
+        self.i2c
+            .write_read(self.address, &[MODE_COMMAND], &mut status)
+            .await?;
+        Ok(status[0])
+    }
+
+    /// Wait until the controller has finished the last instruction, polling the busy flag for at
+    /// most `timeout_us`
+    pub async fn wait_ready(&mut self, timeout_us: u32) -> Result<(), LcdError<B::Error>> {
+        self.flush().await?;
+        let mut waited = 0;
+        while Status::from(self.read_status_byte().await?).busy {
+            if waited >= timeout_us {
+                return Err(LcdError::Timeout);
+            }
+            self.delay.delay_us(BUSY_POLL_US).await;
+            waited += BUSY_POLL_US;
+        }
+        Ok(())
+    }
+
+    /// Read `buffer.len()` characters from DDRAM, starting at a 1-based row and column with the same
+    /// mapping as [`LcdAsync::locate`]. The address counter is left behind the last character read.
+    pub async fn read_ddram(
+        &mut self,
+        row: u8,
+        col: u8,
+        buffer: &mut [u8],
+    ) -> Result<(), LcdError<B::Error>> {
+        self.state
+            .read_ddram(row, col, buffer.len())
+            .ok_or(LcdError::InvalidInputData)?;
+        self.read_data(buffer).await?;
+        self.state.track_ddram_read(buffer.len());
+        Ok(())
+    }
+
+    /// Read the bitmap of custom characters, starting at CGRAM `location` (0-7). Reads past the
+    /// first glyph continue with the following locations.
+    pub async fn read_custom_char(
+        &mut self,
+        location: u8,
+        charmap: &mut [u8],
+    ) -> Result<(), LcdError<B::Error>> {
+        self.state
+            .read_custom_char(location, charmap.len())
+            .ok_or(LcdError::InvalidInputData)?;
+        self.read_data(charmap).await
+    }
+
+    async fn read_data(&mut self, buffer: &mut [u8]) -> Result<(), LcdError<B::Error>> {
+        self.flush().await?;
+        self.i2c
+            .write_read(self.address, &[MODE_DATA], buffer)
+            .await?;
+        Ok(())
+    }
+
+    /// Set the contrast (0-63), kept in the configuration for the next [`LcdAsync::init`]
+    pub async fn set_contrast(&mut self, contrast: u8) -> Result<(), LcdError<B::Error>> {
+        self.state
+            .set_contrast(contrast)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send().await
+    }
+
+    /// Switch the booster circuit on or off
+    pub async fn set_booster(&mut self, booster: bool) -> Result<(), LcdError<B::Error>> {
+        self.state.set_booster(booster);
+        self.send().await
+    }
+
+    /// Switch the voltage follower and select its amplifier ratio
+    pub async fn set_follower(
+        &mut self,
+        follower: FollowerControl,
+    ) -> Result<(), LcdError<B::Error>> {
+        self.state.set_follower(follower);
+        self.send().await
+    }
+
+    /// Set the temperature coefficient TC2-TC0 (0-7) of the LCD drive voltage
+    pub async fn set_temperature_coefficient(&mut self, tc: u8) -> Result<(), LcdError<B::Error>> {
+        self.state
+            .set_temperature_coefficient(tc)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send().await
+    }
+
+    /// Adjust the contrast to a temperature reading in °C. Nothing is sent while the reading stays
+    /// within the hysteresis of `compensation` or the contrast does not change.
+    pub async fn compensate_contrast(
+        &mut self,
+        compensation: &mut TemperatureCompensation<'_>,
+        temperature: i16,
+    ) -> Result<(), LcdError<B::Error>> {
+        let Some(contrast) = compensation.contrast(temperature) else {
+            return Ok(());
+        };
+        let current = self.state.config.power_icon_contrast.contrast;
+        if contrast != current
+            && let Err(err) = self.set_contrast(contrast).await
+        {
+            // The next reading retries, which it skips if the contrast looks set already
+            self.state.config.power_icon_contrast.contrast = current;
+            return Err(err);
+        }
+        compensation.accept(temperature);
+        Ok(())
+    }
+
+    /// Select the lines moved by [`LcdAsync::shift_display`] and by the dot scroll
+    pub async fn enable_line_shift(&mut self, lines: ShiftLines) -> Result<(), LcdError<B::Error>> {
+        self.state.enable_line_shift(lines);
+        self.send().await
+    }
+
+    /// Scroll the lines enabled for shifting by `dots` (0-48) pixels to the left, switching from
+    /// display shift to dot scroll
+    pub async fn set_scroll_quantity(&mut self, dots: u8) -> Result<(), LcdError<B::Error>> {
+        self.state
+            .set_scroll_quantity(dots)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send().await
+    }
+
+    /// Show the whole display in reverse video
+    pub async fn set_inverted(&mut self, inverted: bool) -> Result<(), LcdError<B::Error>> {
+        self.state.set_inverted(inverted);
+        self.send().await
+    }
+
+    /// Let glyph rows with the blink bit set blink, see [`blinking`](crate::cgram::blinking)
+    pub async fn set_data_blink(&mut self, blink: bool) -> Result<(), LcdError<B::Error>> {
+        self.state.set_data_blink(blink);
+        self.send().await
+    }
+
+    /// Select the bias ratio, which is split over two instructions in different banks
+    pub async fn set_bias(&mut self, bias: Bias) -> Result<(), LcdError<B::Error>> {
+        self.state.set_bias(bias);
+        self.send().await
+    }
+
+    /// Start collecting commands and data that go out together, in a single I2C write where
+    /// possible. Nothing is sent until [`BatchAsync::send`] is called, unless the batch outgrows its
+    /// 80 frames and sends part of them early.
+    pub fn batch(&mut self) -> BatchAsync<'_, B, D, RST> {
+        BatchAsync { lcd: self }
+    }
+
+    /// Send everything queued so far, up to [`I2C_BATCH_FRAMES`] frames per write
+    async fn flush(&mut self) -> Result<(), B::Error> {
+        let mut result = Ok(());
+        for chunk in self.state.queue.chunks(I2C_BATCH_FRAMES) {
+            result = self.i2c.write(self.address, &encode_frames(chunk)).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        if !self.state.queue.is_empty() {
+            self.state.sent(result.is_ok());
+        }
+        result
+    }
+
+    /// [`SSD18030Async::flush`] for the public operations
+    async fn send(&mut self) -> Result<(), LcdError<B::Error>> {
+        self.flush().await?;
+        Ok(())
+    }
+
+    /// Write bytes to the data register behind a single control byte
+    async fn write_data(&mut self, data: &[u8]) -> Result<(), B::Error> {
+        // Adjacent writes of a transaction go out back to back without a restart
+        self.i2c
+            .transaction(
+                self.address,
+                &mut [Operation::Write(&[MODE_DATA]), Operation::Write(data)],
+            )
+            .await
+    }
+}
+
+impl<B: I2c, D: DelayNs, RST> LcdAsync for SSD18030Async<B, D, RST> {
+    type Error = LcdError<B::Error>;
+
+    async fn init(&mut self, config: Config) -> Result<(), Self::Error> {
+        self.delay.delay_ms(15).await;
+
+        self.state.init(config);
+        self.flush().await?;
+        self.delay.delay_ms(POWER_UP_MS).await;
+
+        self.state.init_display();
+        self.clear().await?;
+        // Bring back the glyphs and icons the driver holds, the reset did not keep them
+        self.state.restore_cgram();
+        self.send().await?;
+        self.state.restore_segram();
+        self.locate(1, 1).await?;
+        Ok(())
+    }
+
+    async fn clear(&mut self) -> Result<(), Self::Error> {
+        self.execute(Instruction::ClearDisplay).await
+    }
+
+    async fn home(&mut self) -> Result<(), Self::Error> {
+        self.execute(Instruction::ReturnHome).await
+    }
+
+    async fn locate(&mut self, row: u8, col: u8) -> Result<(), Self::Error> {
+        self.state
+            .queue_locate(row, col)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send().await
+    }
+
+    async fn write(&mut self, s: &str) -> Result<(), Self::Error> {
+        let wrap_to_top = match self.state.config.text_mode {
+            TextMode::Raw => return self.write_run(s).await,
+            TextMode::StopAtEnd => false,
+            TextMode::WrapToTop => true,
+        };
+
+        let start = self.text_start().await?;
+        let mut runs = TextRuns::new(s, start, self.state.rows(), wrap_to_top);
+        for step in runs.by_ref() {
+            match step {
+                TextStep::Locate(row) => self
+                    .state
+                    .queue_locate(row, 1)
+                    .ok_or(LcdError::InvalidInputData)?,
+                TextStep::Write(run) => self.write_run(run).await?,
+            }
+        }
+        self.flush().await?;
+        self.state.text_written(runs.position());
+        Ok(())
+    }
+
+    async fn write_raw(&mut self, codes: &[u8]) -> Result<(), Self::Error> {
+        self.write_codes(codes).await
+    }
+
+    async fn set_rom(&mut self, rom: Rom) -> Result<(), Self::Error> {
+        self.state.set_rom(rom);
+        self.send().await
+    }
+
+    async fn sleep(&mut self) -> Result<(), Self::Error> {
+        self.state.sleep();
+        self.send().await
+    }
+
+    async fn wake(&mut self) -> Result<(), Self::Error> {
+        self.state.wake();
+        self.flush().await?;
+        self.delay.delay_ms(POWER_UP_MS).await;
+
+        self.execute(Instruction::DisplayControl(
+            self.state.config.display_control,
+        ))
+        .await
+    }
+
+    async fn set_display(&mut self, flags: DisplayConfig) -> Result<(), Self::Error> {
+        self.state.set_display(flags);
+        self.send().await
+    }
+
+    async fn shift_display(&mut self, direction: HorizontalDir) -> Result<(), Self::Error> {
+        self.state.shift_display(direction);
+        self.send().await
+    }
+
+    async fn shift_cursor(&mut self, direction: HorizontalDir) -> Result<(), Self::Error> {
+        self.execute(Instruction::Shift(ShiftSettings::new(
+            direction,
+            ShiftType::Cursor,
+        )))
+        .await
+    }
+
+    async fn set_entry_mode(&mut self, mode: EntryModeSettings) -> Result<(), Self::Error> {
+        self.state.set_entry_mode(mode);
+        self.send().await
+    }
+
+    async fn set_view_mode(&mut self, mode: ViewMode) -> Result<(), Self::Error> {
+        self.state.set_view_mode(mode);
+        self.send().await
+    }
+
+    async fn set_cursor_off(&mut self) -> Result<(), Self::Error> {
+        self.state
+            .update_display(|display| display.remove(DisplayConfig::CURSOR_ON));
+        self.send().await
+    }
+
+    async fn set_blinking_off(&mut self) -> Result<(), Self::Error> {
+        self.state
+            .update_display(|display| display.remove(DisplayConfig::BLINK_ON));
+        self.send().await
+    }
+
+    async fn set_cursor(&mut self, visible: bool, blinking: bool) -> Result<(), Self::Error> {
+        self.state.update_display(|display| {
+            display.set(DisplayConfig::CURSOR_ON, visible);
+            display.set(DisplayConfig::BLINK_ON, blinking);
+        });
+        self.send().await
+    }
+
+    async fn set_cursor_inverted(&mut self, inverted: bool) -> Result<(), Self::Error> {
+        self.state.set_cursor_inverted(inverted);
+        self.send().await
+    }
+
+    async fn cursor(&mut self) -> Result<Option<(u8, u8)>, Self::Error> {
+        let address = match self.state.address_counter {
+            AddressCounter::Ddram(address) => address,
+            AddressCounter::Cgram | AddressCounter::Segram => return Ok(None),
+            AddressCounter::Unknown => self.read_status().await?.address,
+        };
+        Ok(ddram_position(&self.state.config, address))
+    }
+
+    async fn extended_function_set(&mut self) -> Result<(), Self::Error> {
+        self.state.extended_function_set();
+        self.send().await
+    }
+
+    async fn set_double_height(&mut self, mode: Option<DoubleHeight>) -> Result<(), Self::Error> {
+        self.state
+            .set_double_height(mode)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send().await
+    }
+
+    async fn clear_line(&mut self, line: u8) -> Result<(), Self::Error> {
+        self.clear_chars((line, 1), COLUMNS).await
+    }
+
+    async fn clear_chars(&mut self, row_col: (u8, u8), chars: u8) -> Result<(), Self::Error> {
+        let (row, col) = row_col;
+        self.state
+            .clear_chars(row, col, chars)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send().await
+    }
+
+    async fn write_special_char(&mut self, glyph: &GlyphHandle) -> Result<(), Self::Error> {
+        self.write_codes(&[glyph.code()]).await
+    }
+
+    async fn create_custom_char(
+        &mut self,
+        location: u8,
+        charmap: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.state
+            .create_custom_char(location, charmap)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send().await
+    }
+}
+
+/// Builder collecting commands and data for [`SSD18030Async::batch`].
+///
+/// A batch holds up to 80 frames, one per instruction or data byte plus the function sets an
+/// instruction needs to reach its bank. Adding to a full batch first sends the frames collected so
+/// far, so a longer batch goes out in several parts. Frames that were not sent are discarded when
+/// the batch is dropped, those sent early stay on the display.
+#[must_use]
+pub struct BatchAsync<'a, B: I2c, D: DelayNs, RST> {
+    lcd: &'a mut SSD18030Async<B, D, RST>,
+}
+
+impl<B: I2c, D: DelayNs, RST> BatchAsync<'_, B, D, RST> {
+    /// Add an instruction byte
+    pub async fn command(&mut self, command: u8) -> Result<&mut Self, B::Error> {
+        self.reserve(1).await?;
+        self.lcd.state.queue_raw_command(command);
+        Ok(self)
+    }
+
+    /// Add an instruction, preceded by the function sets needed to reach its RE/IS bank
+    pub async fn instruction(&mut self, instruction: Instruction) -> Result<&mut Self, B::Error> {
+        self.reserve(MAX_INSTRUCTION_FRAMES).await?;
+        self.lcd.state.queue_instruction(instruction);
+        Ok(self)
+    }
+
+    /// Add data bytes, written at the current address counter
+    pub async fn data(&mut self, data: &[u8]) -> Result<&mut Self, B::Error> {
+        self.lcd.state.track_ddram_write(data);
+        for chunk in data.chunks(QUEUE_LEN) {
+            self.reserve(chunk.len()).await?;
+            self.lcd.state.queue_data(chunk);
+        }
+        Ok(self)
+    }
+
+    /// Send all collected frames
+    pub async fn send(&mut self) -> Result<(), B::Error> {
+        self.lcd.flush().await
+    }
+
+    /// Send the collected frames early if `frames` more do not fit into the queue
+    async fn reserve(&mut self, frames: usize) -> Result<(), B::Error> {
+        if self.lcd.state.room() < frames {
+            self.lcd.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+impl<B: I2c, D: DelayNs, RST> Drop for BatchAsync<'_, B, D, RST> {
+    fn drop(&mut self) {
+        if !self.lcd.state.queue.is_empty() {
+            // The address counter tracking already counted the discarded frames
+            self.lcd.state.address_counter = AddressCounter::Unknown;
+            self.lcd.state.queue.clear();
+        }
+    }
+}
@@ -1,29 +1,161 @@
+use crate::cgram::{Glyph, GlyphCache, GlyphHandle, GlyphSlot, StaleCells};
+use crate::charset;
+use crate::commands::{
+    Bias, DoubleHeight, FollowerControl, HorizontalDir, Rom, SSD1803A_PART_ID, ShiftLines,
+    ShiftSettings, ShiftType, Status, ViewMode,
+};
 use crate::commands::{
     CMD_8BIT_4LINES_RE0_IS0, CMD_8BIT_4LINES_RE0_IS1, CMD_8BIT_4LINES_RE1_IS0, CMD_BS0_1,
     CMD_BS1_1, CMD_CLEAR_DISPLAY, CMD_CONTRAST_DEFAULT_DOGS164, CMD_DISPLAY,
-    CMD_FOLLOWER_CONTROL_DOGS164, CMD_POWER_CONTROL_DOGS164, CMD_RETURN_HOME, CMD_ROM_SELECT,
-    COMMAND_2LINES, COMMAND_3LINES_BOTTOM, COMMAND_3LINES_MIDDLE, COMMAND_3LINES_TOP,
-    DisplayConfig, EntryModeSettings, ExtendedFunctionSet, MODE_COMMAND, MODE_DATA,
+    CMD_FOLLOWER_CONTROL_DOGS164, CMD_POWER_CONTROL_DOGS164, DisplayConfig, EntryModeSettings,
 };
-use crate::commands::{DoubleHeight, ViewMode};
-use crate::config::Config;
+use crate::compensation::TemperatureCompensation;
+use crate::config::{Config, TextMode};
+use crate::instruction::{Bank, Instruction};
+use crate::interface::i2c::I2C_ADDRESSES;
+use crate::interface::{I2cInterface, Interface, SpiInterface};
+use crate::segram::{Icons, SEGRAM_SIZE};
+use crate::state::{AddressCounter, MAX_INSTRUCTION_FRAMES, QUEUE_LEN, State};
+use crate::text::{TextRuns, TextStep};
 use embedded_hal::delay::DelayNs;
-use embedded_hal::i2c::{Error as I2cErr, I2c};
+use embedded_hal::digital::OutputPin;
+use embedded_hal::i2c::{Error as _, ErrorKind, I2c};
+use embedded_hal::spi::SpiDevice;
 use heapless::Vec;
 
+/// Worst-case execution time of clear display and return home in µs
+pub(crate) const SLOW_CMD_TIMEOUT_US: u32 = 2_000;
+/// Interval between two busy flag reads in µs
+pub(crate) const BUSY_POLL_US: u32 = 10;
+/// Low time of the /RES pulse in µs
+pub(crate) const RESET_LOW_US: u32 = 1_000;
+/// Time the controller needs after /RES is released in ms
+pub(crate) const RESET_RECOVERY_MS: u32 = 2;
+/// Settling time of the booster and voltage follower in ms
+pub(crate) const POWER_UP_MS: u32 = 100;
+
+/// 8 glyphs of 8 rows each
+pub(crate) const CGRAM_SIZE: usize = 64;
+
+/// Characters encoded before they are written out in one transfer
+pub(crate) const WRITE_CHUNK: usize = 64;
+
+/// Last DDRAM address of a line in 4-line mode
+const LINE_END: u8 = 0x13;
+
+/// Characters per line in 4-line mode. Lines start 0x20 apart, but only the first 20 addresses
+/// are shown and shifted.
+pub const DDRAM_LINE_LEN: u8 = LINE_END + 1;
+
+/// Visible characters per line
+pub const COLUMNS: u8 = 16;
+
+/// DDRAM address the address counter moves to after `address`. In 4-line mode it skips from the
+/// end of a line to the start of the next one.
+pub(crate) fn next_ddram_address(address: u8, direction: HorizontalDir) -> u8 {
+    match direction {
+        HorizontalDir::LeftToRight if address & 0x1F >= LINE_END => (address + 0x20) & 0x60,
+        HorizontalDir::LeftToRight => address + 1,
+        HorizontalDir::RightToLeft if address & 0x1F == 0 => {
+            (address.wrapping_sub(0x20) & 0x60) | LINE_END
+        }
+        HorizontalDir::RightToLeft => address - 1,
+    }
+}
+
+/// DDRAM line shown in each row. A double height row shows the upper one of the two lines it
+/// covers, the lower one is hidden.
+pub(crate) fn row_addresses(double_height: Option<DoubleHeight>) -> &'static [u8] {
+    match double_height {
+        None => &[0x00, 0x20, 0x40, 0x60],
+        Some(DoubleHeight::Lines2) => &[0x00, 0x40],
+        Some(DoubleHeight::Lines3Top) => &[0x00, 0x40, 0x60],
+        Some(DoubleHeight::Lines3Middle) => &[0x00, 0x20, 0x60],
+        Some(DoubleHeight::Lines3Bottom) => &[0x00, 0x20, 0x40],
+    }
+}
+
+/// DDRAM address of a 1-based row and column, `None` if the layout of `config` has no such cell
+pub(crate) fn ddram_address(config: &Config, row: u8, col: u8) -> Option<u8> {
+    if col == 0 || col > COLUMNS {
+        return None;
+    }
+    let line = row_addresses(config.double_height).get(row.checked_sub(1)? as usize)?;
+    Some(line + ddram_offset(config.view_mode) + col - 1)
+}
+
+/// 1-based row and column showing a DDRAM address, `None` if the layout of `config` does not show
+/// it while the display is not shifted
+pub(crate) fn ddram_position(config: &Config, address: u8) -> Option<(u8, u8)> {
+    let lines = row_addresses(config.double_height);
+    let row = lines.iter().position(|&line| line == address & 0x60)?;
+    let col = (address & 0x1F).checked_sub(ddram_offset(config.view_mode))?;
+    (col < COLUMNS).then_some((row as u8 + 1, col + 1))
+}
+
+/// Row and column text in a wrapping [`TextMode`] continues at once the address counter reached
+/// `address`. The column is past the last one when the row before was filled, `None` for other
+/// hidden addresses.
+pub(crate) fn text_position(config: &Config, address: u8) -> Option<(u8, u8)> {
+    ddram_position(config, address).or_else(|| {
+        // Past the last line the counter wraps to 0x00, where clear and home leave it as well
+        if address == 0 {
+            return None;
+        }
+        let previous = next_ddram_address(address, HorizontalDir::RightToLeft);
+        match ddram_position(config, previous)? {
+            (row, COLUMNS) => Some((row, COLUMNS + 1)),
+            _ => None,
+        }
+    })
+}
+
+/// DDRAM address of the first visible column
+pub(crate) fn ddram_offset(view_mode: ViewMode) -> u8 {
+    match view_mode {
+        ViewMode::Top => 0x04,
+        ViewMode::Bottom => 0x00,
+    }
+}
+
 #[derive(Debug)]
-pub enum LcdError<E: I2cErr> {
-    I2c(E),
+pub enum LcdError<E> {
+    /// Error reported by the underlying bus
+    Bus(E),
     InvalidInputData,
+    /// The controller stayed busy for longer than the allowed time
+    Timeout,
+    /// The transport cannot read from the controller
+    ReadUnsupported,
+    /// All CGRAM slots hold other glyphs
+    CgramFull,
 }
 
-impl<E: I2cErr> From<E> for LcdError<E> {
+impl<E> From<E> for LcdError<E> {
     fn from(err: E) -> Self {
-        LcdError::I2c(err)
+        LcdError::Bus(err)
     }
 }
 
+/// Reasons for [`SSD18030::probe_i2c`] to fail
+#[derive(Debug)]
+pub enum ProbeError<E> {
+    /// No device acknowledged on any of the SA0 addresses
+    NotFound,
+    /// A device answered, but its part ID is not the one of an SSD1803A
+    WrongChip { address: u8, part_id: u8 },
+    /// Error reported by the underlying bus
+    Bus(E),
+}
+
 /// Trait defining the LCD operations
+///
+/// Transports that cannot read, the parallel bus and [`SpiInterface::new_write_only`], wait the
+/// worst-case execution time instead of polling the busy flag. [`Lcd::cursor`] and [`Lcd::write`]
+/// in a wrapping [`TextMode`] read the address counter when the driver lost track of it, after
+/// [`SSD18030::send_command`] or [`SSD18030::send_data`], and fail with
+/// [`LcdError::ReadUnsupported`] on those transports until [`Lcd::locate`], [`Lcd::home`] or
+/// [`Lcd::clear`] set it again.
 pub trait Lcd {
     type Error;
 
@@ -35,15 +167,38 @@ pub trait Lcd {
     /// Set cursor to home position
     fn home(&mut self) -> Result<(), Self::Error>;
 
-    /// Set cursor position. Row and column are 1-based.
+    /// Set cursor position. Row and column are 1-based, rows count the lines of the double height
+    /// layout.
     fn locate(&mut self, row: u8, col: u8) -> Result<(), Self::Error>;
 
-    /// Write a string to the display at the current cursor position
+    /// Write a string to the display at the current cursor position, laid out as selected by
+    /// [`Config::text_mode`]. Characters missing from the active ROM are replaced by the fallback
+    /// of the configuration.
     fn write(&mut self, s: &str) -> Result<(), Self::Error>;
 
+    /// Write ROM codes to the display at the current cursor position
+    fn write_raw(&mut self, codes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Switch the character ROM used by the display and by [`Lcd::write`]
+    fn set_rom(&mut self, rom: Rom) -> Result<(), Self::Error>;
+
+    /// Blank the display, switch off the booster and the voltage follower and enter power-down
+    /// mode. The configuration is left as it is for [`Lcd::wake`].
+    fn sleep(&mut self) -> Result<(), Self::Error>;
+
+    /// Leave power-down mode and bring back the display, contrast, booster and follower of the
+    /// configuration
+    fn wake(&mut self) -> Result<(), Self::Error>;
+
     /// Set display control (display on/off, cursor on/off, blink on/off)
     fn set_display(&mut self, flags: DisplayConfig) -> Result<(), Self::Error>;
 
+    /// Shift the lines enabled for shifting by one character, the cursor moves along
+    fn shift_display(&mut self, direction: HorizontalDir) -> Result<(), Self::Error>;
+
+    /// Move the cursor by one character without writing
+    fn shift_cursor(&mut self, direction: HorizontalDir) -> Result<(), Self::Error>;
+
     /// Set entry mode (set cursor/blink direction and enables shift for shift-enabled lines)
     fn set_entry_mode(&mut self, mode: EntryModeSettings) -> Result<(), Self::Error>;
 
@@ -54,316 +209,765 @@ pub trait Lcd {
 
     fn set_blinking_off(&mut self) -> Result<(), Self::Error>;
 
+    /// Show or hide the underline cursor and the blinking block at the cursor position
+    fn set_cursor(&mut self, visible: bool, blinking: bool) -> Result<(), Self::Error>;
+
+    /// Blink the cursor character in reverse video instead of as a black block
+    fn set_cursor_inverted(&mut self, inverted: bool) -> Result<(), Self::Error>;
+
+    /// 1-based row and column of the cursor, from the tracked writes or else from the address
+    /// counter. `None` while the cursor is on a hidden DDRAM address or the address counter
+    /// points to CGRAM or SEGRAM.
+    fn cursor(&mut self) -> Result<Option<(u8, u8)>, Self::Error>;
+
     fn extended_function_set(&mut self) -> Result<(), Self::Error>;
 
-    fn set_double_height(&mut self) -> Result<(), Self::Error>;
+    /// Switch to a double height layout, or back to four lines with `None`
+    fn set_double_height(&mut self, mode: Option<DoubleHeight>) -> Result<(), Self::Error>;
 
     fn clear_line(&mut self, line: u8) -> Result<(), Self::Error>;
 
     fn clear_chars(&mut self, row_col: (u8, u8), chars: u8) -> Result<(), Self::Error>;
 
-    fn write_special_char(&mut self, code: u8) -> Result<(), Self::Error>;
+    /// Write the glyph of a handle from [`SSD18030::alloc_glyph`] at the current cursor position
+    fn write_special_char(&mut self, glyph: &GlyphHandle) -> Result<(), Self::Error>;
 
+    /// Write a bitmap to CGRAM `location` (0-7), bypassing the slots handed out by
+    /// [`SSD18030::alloc_glyph`]
     fn create_custom_char(&mut self, location: u8, charmap: &[u8]) -> Result<(), Self::Error>;
 }
 
-pub struct SSD18030<B: I2c, D: DelayNs> {
-    i2c: B,
+/// Placeholder for a driver without a reset pin, see [`SSD18030::with_reset_pin`]
+pub struct NoResetPin;
 
-    delay: D,
+pub struct SSD18030<IF: Interface, D: DelayNs, RST = NoResetPin> {
+    interface: IF,
 
-    address: u8,
+    delay: D,
 
-    ddram_start: u8,
+    /// /RES pin, active low
+    reset: RST,
 
-    config: Config,
+    /// Configuration, queued frames, register bank, address counter and RAM copies, shared with
+    /// the async driver
+    state: State,
 }
 
-impl<B: I2c, D: DelayNs> SSD18030<B, D> {
+impl<B: I2c, D: DelayNs> SSD18030<I2cInterface<B>, D> {
     pub fn new_i2c(i2c: B, address: u8, delay: D) -> Self {
+        Self::new(I2cInterface::new(i2c, address), delay)
+    }
+
+    /// Look for an SSD1803A on both SA0 addresses and return a driver for the first one whose part
+    /// ID matches. The bus is consumed, pass `&mut i2c` to keep using it after a failed probe.
+    pub fn probe_i2c(mut i2c: B, mut delay: D) -> Result<Self, ProbeError<B::Error>> {
+        let mut wrong_chip = None;
+
+        for address in I2C_ADDRESSES {
+            let mut lcd = Self::new_i2c(i2c, address, delay);
+            match lcd.read_i2c_part_id() {
+                Ok(SSD1803A_PART_ID) => return Ok(lcd),
+                Ok(part_id) => wrong_chip = Some(ProbeError::WrongChip { address, part_id }),
+                Err(err) if matches!(err.kind(), ErrorKind::NoAcknowledge(_)) => {}
+                Err(err) => return Err(ProbeError::Bus(err)),
+            }
+
+            let (interface, released_delay, _) = lcd.release();
+            i2c = interface.release();
+            delay = released_delay;
+        }
+
+        Err(wrong_chip.unwrap_or(ProbeError::NotFound))
+    }
+
+    /// [`SSD18030::read_part_id`] for a transport that always reads
+    fn read_i2c_part_id(&mut self) -> Result<u8, B::Error> {
+        self.state.select_bank(Bank::RE1);
+        self.flush()?;
+        Ok(Status::from(self.interface.read_status_byte()?).address)
+    }
+}
+
+impl<S: SpiDevice, D: DelayNs> SSD18030<SpiInterface<S>, D> {
+    /// Create a driver for a panel strapped for the serial interface
+    pub fn new_spi(spi: S, delay: D) -> Self {
+        Self::new(SpiInterface::new(spi), delay)
+    }
+
+    /// Create a driver for the serial interface in 3-wire mode, where SOD is not connected
+    pub fn new_spi_write_only(spi: S, delay: D) -> Self {
+        Self::new(SpiInterface::new_write_only(spi), delay)
+    }
+}
+
+impl<IF: Interface, D: DelayNs> SSD18030<IF, D> {
+    pub fn new(interface: IF, delay: D) -> Self {
+        let state = State::new(interface.data_length());
         SSD18030 {
-            i2c,
+            interface,
             delay,
-            address,
-            ddram_start: 0x84, // Top view
-            config: Config::default(),
+            reset: NoResetPin,
+            state,
         }
     }
 
-    pub fn send_command(&mut self, command: u8) -> Result<(), B::Error> {
-        let bytes = [MODE_COMMAND, command];
-        self.i2c.write(self.address, &bytes)?;
-        Ok(())
+    /// Attach the GPIO driving the /RES pin, which enables [`SSD18030::hard_reset`]
+    pub fn with_reset_pin<RST: OutputPin>(self, reset: RST) -> SSD18030<IF, D, RST> {
+        SSD18030 {
+            interface: self.interface,
+            delay: self.delay,
+            reset,
+            state: self.state,
+        }
     }
+}
 
-    pub fn send_data_byte(&mut self, data: u8) -> Result<(), B::Error> {
-        let bytes = [MODE_DATA, data];
-        self.i2c.write(self.address, &bytes)?;
+impl<IF: Interface, D: DelayNs, RST: OutputPin> SSD18030<IF, D, RST> {
+    /// Pulse /RES low and wait for the controller to come back up. All registers return to their
+    /// power-on values, so [`Lcd::init`] has to run again afterwards. It also brings back the
+    /// glyphs held by handles and the icons.
+    pub fn hard_reset(&mut self) -> Result<(), RST::Error> {
+        self.state.reset();
+        self.reset.set_low()?;
+        self.delay.delay_us(RESET_LOW_US);
+        self.reset.set_high()?;
+        self.delay.delay_ms(RESET_RECOVERY_MS);
         Ok(())
     }
+}
 
-    pub fn send_data(&mut self, data: &[u8]) -> Result<(), LcdError<B::Error>> {
-        if data.len() > 31 {
-            return Err(LcdError::InvalidInputData);
-        }
+impl<IF: Interface, D: DelayNs, RST> SSD18030<IF, D, RST> {
+    /// Give back the transport, the delay and the reset pin
+    pub fn release(self) -> (IF, D, RST) {
+        (self.interface, self.delay, self.reset)
+    }
 
-        let mut vec: Vec<u8, 32> = Vec::new();
-        vec.push(MODE_DATA).unwrap();
-        vec.extend_from_slice(&data[..data.len()]).unwrap();
-        self.i2c.write(self.address, &vec)?;
-        Ok(())
+    /// Configuration passed to [`Lcd::init`], along with the changes made since
+    pub fn config(&self) -> &Config {
+        &self.state.config
     }
 
-    fn finish_cmd(&mut self) -> Result<(), B::Error> {
-        self.send_command(self.config.display_settings.cmd_re0_is0())?;
-        Ok(())
+    pub fn send_command(&mut self, command: u8) -> Result<(), IF::Error> {
+        self.state.queue_raw_command(command);
+        self.flush()
     }
 
-    fn re0_is0_cmd(&mut self) -> Result<(), B::Error> {
-        self.send_command(self.config.display_settings.cmd_re0_is0())?;
+    /// Send an instruction, preceded by the function sets needed to reach its RE/IS bank. Clear
+    /// display and return home wait for the controller to finish.
+    pub fn execute(&mut self, instruction: Instruction) -> Result<(), LcdError<IF::Error>> {
+        self.state.queue_instruction(instruction);
+        match instruction {
+            Instruction::ClearDisplay | Instruction::ReturnHome => {
+                self.wait_ready(SLOW_CMD_TIMEOUT_US)?
+            }
+            _ => self.flush()?,
+        }
         Ok(())
     }
 
-    fn re0_is1_cmd(&mut self) -> Result<(), B::Error> {
-        self.send_command(self.config.display_settings.cmd_re0_is1())?;
+    pub fn send_data_byte(&mut self, data: u8) -> Result<(), IF::Error> {
+        self.state.untracked_data();
+        self.flush()?;
+        self.interface.send_data(&[data])
+    }
+
+    pub fn send_data(&mut self, data: &[u8]) -> Result<(), LcdError<IF::Error>> {
+        self.state.untracked_data();
+        self.flush()?;
+        self.interface.send_data(data)?;
         Ok(())
     }
 
-    fn re1_is0_cmd(&mut self) -> Result<(), B::Error> {
-        self.send_command(self.config.display_settings.cmd_re1_is0())?;
+    /// Row and column text in a wrapping [`TextMode`] continues at
+    fn text_start(&mut self) -> Result<(u8, u8), LcdError<IF::Error>> {
+        let address = match self.state.address_counter {
+            AddressCounter::Ddram(address) => Some(address),
+            AddressCounter::Cgram | AddressCounter::Segram => None,
+            AddressCounter::Unknown => Some(self.read_status()?.address),
+        };
+        Ok(self.state.text_start(address))
+    }
+
+    /// Encode and write characters at the address counter, regardless of the text mode
+    pub(crate) fn write_run(&mut self, s: &str) -> Result<(), LcdError<IF::Error>> {
+        let mut codes: Vec<u8, WRITE_CHUNK> = Vec::new();
+        for c in s.chars() {
+            if codes.is_full() {
+                self.write_codes(&codes)?;
+                codes.clear();
+            }
+            let code = match charset::encode(self.state.config.charset, c) {
+                Some(code) => code,
+                None => self.fallback_code(c, &mut codes)?,
+            };
+            codes.push(code).unwrap();
+        }
+
+        if !codes.is_empty() {
+            self.write_codes(&codes)?;
+        }
         Ok(())
     }
 
-    fn re1_is1_cmd(&mut self) -> Result<(), B::Error> {
-        self.send_command(self.config.display_settings.cmds_re1_is1()[0])?;
-        self.send_command(self.config.display_settings.cmds_re1_is1()[1])?;
+    /// Write character codes to DDRAM at the address counter
+    fn write_codes(&mut self, codes: &[u8]) -> Result<(), LcdError<IF::Error>> {
+        self.flush()?;
+        self.interface.send_data(codes)?;
+        self.state.track_ddram_write(codes);
         Ok(())
     }
 
-    fn set_bias(&mut self) -> Result<(), B::Error> {
-        self.send_command(self.config.display_settings.cmd_re1_is0())?;
-        self.send_command(CMD_BS1_1)?;
-        self.send_command(self.config.display_settings.cmd_re0_is1())?;
-        self.send_command(CMD_BS0_1)?;
+    /// Code for a character missing from the ROM. A glyph that is not loaded yet is uploaded
+    /// after the codes in `pending` went out, since the upload moves the address counter.
+    fn fallback_code(
+        &mut self,
+        c: char,
+        pending: &mut Vec<u8, WRITE_CHUNK>,
+    ) -> Result<u8, LcdError<IF::Error>> {
+        match self.state.fallback_slot(c) {
+            None => Ok(self.state.config.fallback),
+            Some(GlyphSlot::Loaded(slot)) => Ok(slot),
+            Some(GlyphSlot::Upload(slot, glyph)) => {
+                if !pending.is_empty() {
+                    self.write_codes(pending)?;
+                    pending.clear();
+                }
+                self.state.queue_glyph_upload(slot, glyph);
+                Ok(slot)
+            }
+        }
+    }
+
+    /// Read the busy flag and address counter, fails with [`LcdError::ReadUnsupported`] on
+    /// transports that cannot read
+    pub fn read_status(&mut self) -> Result<Status, LcdError<IF::Error>> {
+        self.state.select_bank(Bank::RE0);
+        Ok(Status::from(self.read_status_byte()?))
+    }
+
+    /// Read the part ID, which the controller reports instead of the address counter while RE = 1.
+    /// Fails with [`LcdError::ReadUnsupported`] on transports that cannot read.
+    pub fn read_part_id(&mut self) -> Result<u8, LcdError<IF::Error>> {
+        self.state.select_bank(Bank::RE1);
+        Ok(Status::from(self.read_status_byte()?).address)
+    }
+
+    fn read_status_byte(&mut self) -> Result<u8, LcdError<IF::Error>> {
+        self.flush()?;
+        self.interface
+            .read_status()?
+            .ok_or(LcdError::ReadUnsupported)
+    }
+
+    /// Wait until the controller has finished the last instruction, polling the busy flag for at
+    /// most `timeout_us`. Transports that cannot read wait the whole `timeout_us` instead.
+    pub fn wait_ready(&mut self, timeout_us: u32) -> Result<(), LcdError<IF::Error>> {
+        self.flush()?;
+        let mut waited = 0;
+        while let Some(status) = self.interface.read_status()? {
+            if !Status::from(status).busy {
+                return Ok(());
+            }
+            if waited >= timeout_us {
+                return Err(LcdError::Timeout);
+            }
+            self.delay.delay_us(BUSY_POLL_US);
+            waited += BUSY_POLL_US;
+        }
+
+        self.delay.delay_us(timeout_us);
         Ok(())
     }
 
-    fn select_romA(&mut self) -> Result<(), B::Error> {
-        self.send_command(self.config.display_settings.cmd_re1_is0())?;
-        self.send_command(CMD_ROM_SELECT)?; // Set ROM to ROM A
-        self.send_data_byte(0x00)?; // ROM A
-        self.finish_cmd()?;
+    /// Read `buffer.len()` characters from DDRAM, starting at a 1-based row and column with the same
+    /// mapping as [`Lcd::locate`]. The address counter is left behind the last character read.
+    /// Fails with [`LcdError::ReadUnsupported`] on transports that cannot read.
+    pub fn read_ddram(
+        &mut self,
+        row: u8,
+        col: u8,
+        buffer: &mut [u8],
+    ) -> Result<(), LcdError<IF::Error>> {
+        self.state
+            .read_ddram(row, col, buffer.len())
+            .ok_or(LcdError::InvalidInputData)?;
+        self.read_data(buffer)?;
+        self.state.track_ddram_read(buffer.len());
         Ok(())
     }
 
-    pub fn setup(&mut self) -> Result<(), B::Error> {
-        self.send_command(CMD_8BIT_4LINES_RE0_IS0)?;
-        self.send_command(0x06)?;
-        self.send_command(CMD_8BIT_4LINES_RE1_IS0)?;
-        self.send_command(0x09)?;
-        self.send_command(ViewMode::Top as u8)?;
-        self.send_command(CMD_BS1_1)?;
-        self.send_command(CMD_8BIT_4LINES_RE0_IS1)?;
-        self.send_command(CMD_BS0_1)?;
-        self.send_command(CMD_FOLLOWER_CONTROL_DOGS164)?;
-        self.send_command(CMD_POWER_CONTROL_DOGS164)?;
-        self.send_command(CMD_CONTRAST_DEFAULT_DOGS164)?;
-        self.send_command(CMD_8BIT_4LINES_RE0_IS0)?;
-        let display_cfg =
-            DisplayConfig::DISPLAY_ON | DisplayConfig::CURSOR_ON | DisplayConfig::BLINK_ON;
-        self.send_command(CMD_DISPLAY | display_cfg.bits())?;
-        self.send_command(CMD_8BIT_4LINES_RE0_IS0)?;
-        // self.send_command(0x84)?;
-        self.delay.delay_ms(100);
-        self.send_command(CMD_CLEAR_DISPLAY)?;
+    /// Read the bitmap of custom characters, starting at CGRAM `location` (0-7). Reads past the
+    /// first glyph continue with the following locations.
+    pub fn read_custom_char(
+        &mut self,
+        location: u8,
+        charmap: &mut [u8],
+    ) -> Result<(), LcdError<IF::Error>> {
+        self.state
+            .read_custom_char(location, charmap.len())
+            .ok_or(LcdError::InvalidInputData)?;
+        self.read_data(charmap)
+    }
+
+    /// Set the contrast (0-63), kept in the configuration for the next [`Lcd::init`]
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), LcdError<IF::Error>> {
+        self.state
+            .set_contrast(contrast)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send()
+    }
+
+    /// Switch the booster circuit on or off
+    pub fn set_booster(&mut self, booster: bool) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_booster(booster);
+        self.send()
+    }
+
+    /// Switch the voltage follower and select its amplifier ratio
+    pub fn set_follower(&mut self, follower: FollowerControl) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_follower(follower);
+        self.send()
+    }
+
+    /// Set the temperature coefficient TC2-TC0 (0-7) of the LCD drive voltage
+    pub fn set_temperature_coefficient(&mut self, tc: u8) -> Result<(), LcdError<IF::Error>> {
+        self.state
+            .set_temperature_coefficient(tc)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send()
+    }
+
+    /// Adjust the contrast to a temperature reading in °C. Nothing is sent while the reading stays
+    /// within the hysteresis of `compensation` or the contrast does not change.
+    pub fn compensate_contrast(
+        &mut self,
+        compensation: &mut TemperatureCompensation<'_>,
+        temperature: i16,
+    ) -> Result<(), LcdError<IF::Error>> {
+        let Some(contrast) = compensation.contrast(temperature) else {
+            return Ok(());
+        };
+        let current = self.state.config.power_icon_contrast.contrast;
+        if contrast != current
+            && let Err(err) = self.set_contrast(contrast)
+        {
+            // The next reading retries, which it skips if the contrast looks set already
+            self.state.config.power_icon_contrast.contrast = current;
+            return Err(err);
+        }
+        compensation.accept(temperature);
         Ok(())
     }
-}
 
-impl<B: I2c, D: DelayNs> Lcd for SSD18030<B, D> {
-    type Error = LcdError<B::Error>;
+    /// Select the lines moved by [`Lcd::shift_display`] and by the dot scroll
+    pub fn enable_line_shift(&mut self, lines: ShiftLines) -> Result<(), LcdError<IF::Error>> {
+        self.state.enable_line_shift(lines);
+        self.send()
+    }
 
-    fn init(&mut self, config: Config) -> Result<(), Self::Error> {
-        self.delay.delay_ms(15);
-        self.set_entry_mode(config.entry_mode)?;
+    /// Scroll the lines enabled for shifting by `dots` (0-48) pixels to the left, switching from
+    /// display shift to dot scroll
+    pub fn set_scroll_quantity(&mut self, dots: u8) -> Result<(), LcdError<IF::Error>> {
+        self.state
+            .set_scroll_quantity(dots)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send()
+    }
 
-        self.delay.delay_ms(100);
-        self.set_view_mode(config.view_mode)?;
+    /// Show the whole display in reverse video
+    pub fn set_inverted(&mut self, inverted: bool) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_inverted(inverted);
+        self.send()
+    }
 
-        self.delay.delay_ms(100);
-        self.set_double_height()?;
+    /// Let glyph rows with the blink bit set blink, see [`blinking`](crate::cgram::blinking)
+    pub fn set_data_blink(&mut self, blink: bool) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_data_blink(blink);
+        self.send()
+    }
 
-        self.delay.delay_ms(100);
-        self.extended_function_set()?;
+    /// Select the bias ratio, which is split over two instructions in different banks
+    pub fn set_bias(&mut self, bias: Bias) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_bias(bias);
+        self.send()
+    }
 
-        self.delay.delay_ms(100);
-        self.set_bias()?;
+    /// Substitute characters missing from the ROM with the glyphs of `glyphs` in [`Lcd::write`].
+    /// The glyphs are loaded into CGRAM as they are needed, replacing the least recently used one
+    /// once all 8 slots are taken, so custom characters created before are overwritten.
+    pub fn enable_fallback_glyphs(&mut self, glyphs: &'static [(char, Glyph)]) {
+        self.state.glyph_cache = Some(GlyphCache::new(glyphs));
+    }
 
-        self.delay.delay_ms(100);
-        self.send_command(self.config.display_settings.cmd_re0_is1())?;
+    /// Take a CGRAM slot showing `glyph`. A slot that already shows the same bitmap is shared
+    /// instead of taking another one, slots used for fallback glyphs are taken over when no other
+    /// slot is free. After an upload the address counter is pointed back to the DDRAM address it
+    /// had, unless the driver lost track of it after raw commands or data.
+    pub fn alloc_glyph(&mut self, glyph: &Glyph) -> Result<GlyphHandle, LcdError<IF::Error>> {
+        let handle = self.state.alloc_glyph(glyph).ok_or(LcdError::CgramFull)?;
 
-        self.delay.delay_ms(100);
-        self.send_command(CMD_FOLLOWER_CONTROL_DOGS164)?;
+        if let Err(err) = self.send() {
+            self.state.cgram.release(handle);
+            return Err(err);
+        }
+        Ok(handle)
+    }
 
-        self.delay.delay_ms(100);
-        self.send_command(CMD_POWER_CONTROL_DOGS164)?;
+    /// Give back a handle from [`SSD18030::alloc_glyph`]. Its slot is free for other glyphs once
+    /// all handles to it are released, cells still showing it change along with the slot.
+    pub fn release_glyph(&mut self, glyph: GlyphHandle) {
+        self.state.cgram.release(glyph);
+    }
 
-        self.delay.delay_ms(100);
-        self.send_command(CMD_CONTRAST_DEFAULT_DOGS164)?;
+    /// Go back to the fallback character of the configuration for missing characters
+    pub fn disable_fallback_glyphs(&mut self) {
+        self.state.glyph_cache = None;
+    }
 
-        self.delay.delay_ms(100);
-        self.set_display(config.display_control)?;
+    /// Cells (row, column) still showing a fallback glyph whose slot was since given to another
+    /// character, so they now show that one. Each cell is reported once, the application rewrites
+    /// them as needed.
+    pub fn take_stale_cells(&mut self) -> StaleCells {
+        self.state.take_stale_cells()
+    }
 
-        self.delay.delay_ms(100);
-        self.select_romA()?;
+    /// Select how [`Lcd::write`] lays out text
+    pub fn set_text_mode(&mut self, text_mode: TextMode) {
+        self.state.config.text_mode = text_mode;
+    }
 
-        self.delay.delay_ms(100);
-        self.locate(1, 1)?;
+    /// Show or hide the icons in SEGRAM
+    pub fn set_icons_enabled(&mut self, enabled: bool) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_icons_enabled(enabled);
+        self.send()
+    }
 
-        self.delay.delay_ms(100);
-        self.clear()?;
-        Ok(())
+    /// SEGRAM contents as written by the driver
+    pub fn icons(&self) -> &Icons {
+        &self.state.icons
     }
 
-    fn clear(&mut self) -> Result<(), LcdError<B::Error>> {
-        self.send_command(CMD_CLEAR_DISPLAY)?;
-        Ok(())
+    /// Switch an icon on or off, see [`segram`](crate::segram) for the numbering
+    pub fn set_icon(&mut self, index: u8, on: bool) -> Result<(), LcdError<IF::Error>> {
+        self.state
+            .set_icon(index, on)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send()
     }
 
-    fn home(&mut self) -> Result<(), LcdError<B::Error>> {
-        self.send_command(CMD_RETURN_HOME)?;
-        Ok(())
+    /// Let an icon blink while data blink is enabled. All five icons sharing its SEGRAM address
+    /// blink together.
+    pub fn set_icon_blink(&mut self, index: u8, blink: bool) -> Result<(), LcdError<IF::Error>> {
+        self.state
+            .set_icon_blink(index, blink)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send()
     }
 
-    fn locate(&mut self, row: u8, col: u8) -> Result<(), LcdError<B::Error>> {
-        if col > 16 || col == 0 || row == 0 || row > 4 {
-            return Err(LcdError::InvalidInputData);
-        }
+    /// Replace the whole SEGRAM
+    pub fn write_icons(&mut self, data: &[u8; SEGRAM_SIZE]) -> Result<(), LcdError<IF::Error>> {
+        self.state.write_icons(data);
+        self.send()
+    }
 
-        let col = col - 1; // Convert to 0-based index
+    fn read_data(&mut self, buffer: &mut [u8]) -> Result<(), LcdError<IF::Error>> {
+        self.flush()?;
+        if self.interface.read_data(buffer)? {
+            Ok(())
+        } else {
+            Err(LcdError::ReadUnsupported)
+        }
+    }
 
-        let addr = match row {
-            1 => col,
-            2 => 0x20 + col,
-            3 => 0x40 + col,
-            4 => 0x60 + col,
-            _ => return Err(LcdError::InvalidInputData),
-        };
+    /// Start collecting commands and data that go out together, in a single I2C write where
+    /// possible. Nothing is sent until [`Batch::send`] is called, unless the batch outgrows its
+    /// 80 frames and sends part of them early.
+    pub fn batch(&mut self) -> Batch<'_, IF, D, RST> {
+        Batch { lcd: self }
+    }
 
-        let mut start = 0x80;
-        if self.config.view_mode == ViewMode::Top {
-            start = start + 0x04;
+    /// Send everything queued so far
+    fn flush(&mut self) -> Result<(), IF::Error> {
+        if self.state.queue.is_empty() {
+            return Ok(());
         }
+        let result = self.interface.send_batch(&self.state.queue);
+        self.state.sent(result.is_ok());
+        result
+    }
 
-        self.send_command(start + addr)?;
+    /// [`SSD18030::flush`] for the public operations
+    fn send(&mut self) -> Result<(), LcdError<IF::Error>> {
+        self.flush()?;
         Ok(())
     }
 
-    fn write(&mut self, s: &str) -> Result<(), LcdError<B::Error>> {
-        let bytes = s.as_bytes();
-        self.send_data(bytes)?;
+    pub fn setup(&mut self) -> Result<(), LcdError<IF::Error>> {
+        self.state.address_counter = AddressCounter::Unknown;
+        self.interface.init()?;
+        let state = &mut self.state;
+        state.function_set(CMD_8BIT_4LINES_RE0_IS0);
+        state.queue_command(0x06);
+        state.function_set(CMD_8BIT_4LINES_RE1_IS0);
+        state.queue_command(0x09);
+        state.queue_command(ViewMode::Top as u8);
+        state.queue_command(CMD_BS1_1);
+        state.function_set(CMD_8BIT_4LINES_RE0_IS1);
+        state.queue_command(CMD_BS0_1);
+        state.queue_command(CMD_FOLLOWER_CONTROL_DOGS164);
+        state.queue_command(CMD_POWER_CONTROL_DOGS164);
+        state.queue_command(CMD_CONTRAST_DEFAULT_DOGS164);
+        state.function_set(CMD_8BIT_4LINES_RE0_IS0);
+        let display_cfg =
+            DisplayConfig::DISPLAY_ON | DisplayConfig::CURSOR_ON | DisplayConfig::BLINK_ON;
+        state.queue_command(CMD_DISPLAY | display_cfg.bits());
+        state.config.display_control = display_cfg;
+        state.function_set(CMD_8BIT_4LINES_RE0_IS0);
+        // state.queue_command(0x84);
+        self.flush()?;
+        self.delay.delay_ms(100);
+        self.state.queue_command(CMD_CLEAR_DISPLAY);
+        self.wait_ready(SLOW_CMD_TIMEOUT_US)?;
         Ok(())
     }
+}
 
-    fn set_display(&mut self, flags: DisplayConfig) -> Result<(), LcdError<B::Error>> {
-        self.re0_is0_cmd()?;
-        self.send_command(CMD_DISPLAY | flags.bits())?;
+impl<IF: Interface, D: DelayNs, RST> Lcd for SSD18030<IF, D, RST> {
+    type Error = LcdError<IF::Error>;
+
+    fn init(&mut self, config: Config) -> Result<(), Self::Error> {
+        self.delay.delay_ms(15);
+        self.interface.init()?;
+
+        self.state.init(config);
+        self.flush()?;
+        self.delay.delay_ms(POWER_UP_MS);
+
+        self.state.init_display();
+        self.clear()?;
+        // Bring back the glyphs and icons the driver holds, the reset did not keep them
+        self.state.restore_cgram();
+        self.send()?;
+        self.state.restore_segram();
+        self.locate(1, 1)?;
         Ok(())
     }
 
-    fn set_entry_mode(&mut self, mode: EntryModeSettings) -> Result<(), LcdError<B::Error>> {
-        self.re0_is0_cmd()?;
-        self.send_command(mode.cmd())?;
-        Ok(())
+    fn clear(&mut self) -> Result<(), LcdError<IF::Error>> {
+        self.execute(Instruction::ClearDisplay)
+    }
+
+    fn home(&mut self) -> Result<(), LcdError<IF::Error>> {
+        self.execute(Instruction::ReturnHome)
     }
 
-    fn set_view_mode(&mut self, mode: ViewMode) -> Result<(), LcdError<B::Error>> {
-        match mode {
-            ViewMode::Top => self.ddram_start = 0x84,
-            ViewMode::Bottom => self.ddram_start = 0x80,
+    fn locate(&mut self, row: u8, col: u8) -> Result<(), LcdError<IF::Error>> {
+        self.state
+            .queue_locate(row, col)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send()
+    }
+
+    fn write(&mut self, s: &str) -> Result<(), LcdError<IF::Error>> {
+        let wrap_to_top = match self.state.config.text_mode {
+            TextMode::Raw => return self.write_run(s),
+            TextMode::StopAtEnd => false,
+            TextMode::WrapToTop => true,
+        };
+
+        let start = self.text_start()?;
+        let mut runs = TextRuns::new(s, start, self.state.rows(), wrap_to_top);
+        for step in runs.by_ref() {
+            match step {
+                TextStep::Locate(row) => self
+                    .state
+                    .queue_locate(row, 1)
+                    .ok_or(LcdError::InvalidInputData)?,
+                TextStep::Write(run) => self.write_run(run)?,
+            }
         }
-        self.re1_is0_cmd()?;
-        self.send_command(mode as u8)?;
+        self.flush()?;
+        self.state.text_written(runs.position());
         Ok(())
     }
 
+    fn write_raw(&mut self, codes: &[u8]) -> Result<(), LcdError<IF::Error>> {
+        self.write_codes(codes)
+    }
+
+    fn set_rom(&mut self, rom: Rom) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_rom(rom);
+        self.send()
+    }
+
+    fn sleep(&mut self) -> Result<(), Self::Error> {
+        self.state.sleep();
+        self.send()
+    }
+
+    fn wake(&mut self) -> Result<(), Self::Error> {
+        self.state.wake();
+        self.flush()?;
+        self.delay.delay_ms(POWER_UP_MS);
+
+        self.execute(Instruction::DisplayControl(
+            self.state.config.display_control,
+        ))
+    }
+
+    fn set_display(&mut self, flags: DisplayConfig) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_display(flags);
+        self.send()
+    }
+
+    fn shift_display(&mut self, direction: HorizontalDir) -> Result<(), Self::Error> {
+        self.state.shift_display(direction);
+        self.send()
+    }
+
+    fn shift_cursor(&mut self, direction: HorizontalDir) -> Result<(), Self::Error> {
+        self.execute(Instruction::Shift(ShiftSettings::new(
+            direction,
+            ShiftType::Cursor,
+        )))
+    }
+
+    fn set_entry_mode(&mut self, mode: EntryModeSettings) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_entry_mode(mode);
+        self.send()
+    }
+
+    fn set_view_mode(&mut self, mode: ViewMode) -> Result<(), LcdError<IF::Error>> {
+        self.state.set_view_mode(mode);
+        self.send()
+    }
+
     fn set_cursor_off(&mut self) -> Result<(), Self::Error> {
-        self.config.display_control.remove(DisplayConfig::CURSOR_ON);
-        self.send_command(CMD_DISPLAY | self.config.display_control.bits())?;
-        Ok(())
+        self.state
+            .update_display(|display| display.remove(DisplayConfig::CURSOR_ON));
+        self.send()
     }
 
     fn set_blinking_off(&mut self) -> Result<(), Self::Error> {
-        self.config.display_control.remove(DisplayConfig::BLINK_ON);
-        self.send_command(CMD_DISPLAY | self.config.display_control.bits())?;
-        Ok(())
+        self.state
+            .update_display(|display| display.remove(DisplayConfig::BLINK_ON));
+        self.send()
+    }
+
+    fn set_cursor(&mut self, visible: bool, blinking: bool) -> Result<(), Self::Error> {
+        self.state.update_display(|display| {
+            display.set(DisplayConfig::CURSOR_ON, visible);
+            display.set(DisplayConfig::BLINK_ON, blinking);
+        });
+        self.send()
+    }
+
+    fn set_cursor_inverted(&mut self, inverted: bool) -> Result<(), Self::Error> {
+        self.state.set_cursor_inverted(inverted);
+        self.send()
+    }
+
+    fn cursor(&mut self) -> Result<Option<(u8, u8)>, Self::Error> {
+        let address = match self.state.address_counter {
+            AddressCounter::Ddram(address) => address,
+            AddressCounter::Cgram | AddressCounter::Segram => return Ok(None),
+            AddressCounter::Unknown => self.read_status()?.address,
+        };
+        Ok(ddram_position(&self.state.config, address))
     }
 
     fn extended_function_set(&mut self) -> Result<(), Self::Error> {
-        self.re1_is0_cmd()?;
-        let cmd = ExtendedFunctionSet::new(
-            self.config.font_width,
-            self.config.bw_inversion,
-            self.config.four_line_enabled,
-        )
-        .cmd();
-        self.send_command(cmd)?;
-        Ok(())
+        self.state.extended_function_set();
+        self.send()
     }
 
-    fn set_double_height(&mut self) -> Result<(), Self::Error> {
-        if let Some(dh) = self.config.double_height {
-            let cmd = match dh {
-                DoubleHeight::Lines2 => COMMAND_2LINES,
-                DoubleHeight::Lines3Middle => COMMAND_3LINES_MIDDLE,
-                DoubleHeight::Lines3Top => COMMAND_3LINES_TOP,
-                DoubleHeight::Lines3Bottom => COMMAND_3LINES_BOTTOM,
-            };
-            self.send_command(0x3A)?;
-            self.send_command(cmd)?;
-            self.send_command(0x3C)?;
-        }
-        Ok(())
+    fn set_double_height(&mut self, mode: Option<DoubleHeight>) -> Result<(), Self::Error> {
+        self.state
+            .set_double_height(mode)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send()
     }
 
     fn clear_line(&mut self, line: u8) -> Result<(), Self::Error> {
-        if line == 0 || line > 4 {
-            return Err(LcdError::InvalidInputData);
-        }
-
-        self.locate(line, 1)?;
-        for _ in 0..16 {
-            self.send_data_byte(b' ')?;
-        }
-        self.locate(line, 1)?;
-        Ok(())
+        self.clear_chars((line, 1), COLUMNS)
     }
 
     fn clear_chars(&mut self, row_col: (u8, u8), chars: u8) -> Result<(), Self::Error> {
         let (row, col) = row_col;
-        if col == 0 || col > 16 || row == 0 || row > 4 || chars == 0 || chars > 16 {
-            return Err(LcdError::InvalidInputData);
-        }
+        self.state
+            .clear_chars(row, col, chars)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send()
+    }
+
+    fn write_special_char(&mut self, glyph: &GlyphHandle) -> Result<(), LcdError<IF::Error>> {
+        self.write_codes(&[glyph.code()])
+    }
+
+    fn create_custom_char(&mut self, location: u8, charmap: &[u8]) -> Result<(), Self::Error> {
+        self.state
+            .create_custom_char(location, charmap)
+            .ok_or(LcdError::InvalidInputData)?;
+        self.send()
+    }
+}
 
-        self.locate(row, col)?;
-        for _ in 0..chars {
-            self.send_data_byte(b' ')?;
+/// Builder collecting commands and data for [`SSD18030::batch`].
+///
+/// A batch holds up to 80 frames, one per instruction or data byte plus the function sets an
+/// instruction needs to reach its bank. Adding to a full batch first sends the frames collected so
+/// far, so a longer batch goes out in several parts. Frames that were not sent are discarded when
+/// the batch is dropped, those sent early stay on the display.
+#[must_use]
+pub struct Batch<'a, IF: Interface, D: DelayNs, RST> {
+    lcd: &'a mut SSD18030<IF, D, RST>,
+}
+
+impl<IF: Interface, D: DelayNs, RST> Batch<'_, IF, D, RST> {
+    /// Add an instruction byte
+    pub fn command(&mut self, command: u8) -> Result<&mut Self, IF::Error> {
+        self.reserve(1)?;
+        self.lcd.state.queue_raw_command(command);
+        Ok(self)
+    }
+
+    /// Add an instruction, preceded by the function sets needed to reach its RE/IS bank
+    pub fn instruction(&mut self, instruction: Instruction) -> Result<&mut Self, IF::Error> {
+        self.reserve(MAX_INSTRUCTION_FRAMES)?;
+        self.lcd.state.queue_instruction(instruction);
+        Ok(self)
+    }
+
+    /// Add data bytes, written at the current address counter
+    pub fn data(&mut self, data: &[u8]) -> Result<&mut Self, IF::Error> {
+        self.lcd.state.track_ddram_write(data);
+        for chunk in data.chunks(QUEUE_LEN) {
+            self.reserve(chunk.len())?;
+            self.lcd.state.queue_data(chunk);
         }
+        Ok(self)
+    }
 
-        self.locate(row, col)?;
-        Ok(())
+    /// Send all collected frames
+    pub fn send(&mut self) -> Result<(), IF::Error> {
+        self.lcd.flush()
     }
 
-    fn write_special_char(&mut self, code: u8) -> Result<(), LcdError<B::Error>> {
-        self.send_data_byte(code)?;
+    /// Send the collected frames early if `frames` more do not fit into the queue
+    fn reserve(&mut self, frames: usize) -> Result<(), IF::Error> {
+        if self.lcd.state.room() < frames {
+            self.lcd.flush()?;
+        }
         Ok(())
     }
+}
 
-    fn create_custom_char(&mut self, location: u8, charmap: &[u8]) -> Result<(), Self::Error> {
-        let loc = location & 0x07; // Only 8 locations (0-7)
-        self.re0_is0_cmd()?;
-        self.send_command(0x40 | (loc << 3))?; // Set
-        self.send_data(charmap)?;
-        Ok(())
+impl<IF: Interface, D: DelayNs, RST> Drop for Batch<'_, IF, D, RST> {
+    fn drop(&mut self) {
+        if !self.lcd.state.queue.is_empty() {
+            // The address counter tracking already counted the discarded frames
+            self.lcd.state.address_counter = AddressCounter::Unknown;
+            self.lcd.state.queue.clear();
+        }
     }
 }
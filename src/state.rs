@@ -0,0 +1,654 @@
+//! Driver state shared by the blocking and the async driver.
+//!
+//! Everything that does not need the bus lives here: the configuration, the frames waiting to go
+//! out, the register bank and address counter the controller is left with once they are sent, and
+//! the driver's copies of CGRAM and SEGRAM. [`SSD18030`](crate::ssd18030_i2c::SSD18030) and the
+//! async driver only add the transfers, reads and delays.
+use crate::cgram::{CgramAllocator, Glyph, GlyphCache, GlyphHandle, GlyphSlot, StaleCells};
+use crate::commands::{
+    Bias, DataLength, DisplayConfig, DoubleHeight, DoubleHeightBiasDisplayShift, EntryModeSettings,
+    ExtendedFunctionSet, FUNCTION_SET_DL, FollowerControl, HorizontalDir, LineDisplayMode,
+    MAX_SCROLL_DOTS, OscillatorSettings, PowerIconContrast, Rom, ShiftLines, ShiftSettings,
+    ShiftType, ViewMode,
+};
+use crate::config::Config;
+use crate::instruction::{Bank, FUNCTION_SET, FUNCTION_SET_MASK, Instruction};
+use crate::interface::Frame;
+use crate::segram::{Icons, SEGRAM_SIZE};
+use crate::ssd18030_i2c::{
+    CGRAM_SIZE, ddram_address, ddram_offset, next_ddram_address, row_addresses, text_position,
+};
+use heapless::Vec;
+
+/// Frames an operation queues at most before they are sent. The longest one uploads all 8 glyphs
+/// at once.
+pub(crate) const QUEUE_LEN: usize = 80;
+
+/// Frames of a single instruction: up to two function sets, the code and its parameter
+pub(crate) const MAX_INSTRUCTION_FRAMES: usize = 4;
+
+/// What the driver knows about the address counter of the controller
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum AddressCounter {
+    /// At this DDRAM address
+    Ddram(u8),
+
+    /// Somewhere in CGRAM
+    Cgram,
+
+    /// Somewhere in SEGRAM
+    Segram,
+
+    /// Lost track after raw commands or data, only a status read tells
+    Unknown,
+}
+
+impl AddressCounter {
+    /// Address counter after `instruction`
+    fn after(self, instruction: Instruction) -> Self {
+        match instruction {
+            Instruction::SetDdramAddress(address) => Self::Ddram(address & 0x7F),
+            Instruction::ClearDisplay | Instruction::ReturnHome => Self::Ddram(0),
+            Instruction::SetCgramAddress(_) => Self::Cgram,
+            Instruction::SetSegramAddress(_) => Self::Segram,
+            Instruction::Shift(shift) if shift.shift_type == ShiftType::Cursor => match self {
+                Self::Ddram(address) => Self::Ddram(next_ddram_address(address, shift.mode)),
+                other => other,
+            },
+            _ => self,
+        }
+    }
+}
+
+pub(crate) struct State {
+    pub(crate) config: Config,
+
+    /// Width of the data bus, reflected in the DL bit of every function set
+    data_length: DataLength,
+
+    /// Frames waiting to go out in one transfer
+    pub(crate) queue: Vec<Frame, QUEUE_LEN>,
+
+    /// RE and IS bits the controller was left with by the frames sent so far
+    bank: Bank,
+
+    /// Address counter once the queued frames are sent
+    pub(crate) address_counter: AddressCounter,
+
+    /// Position reached by the last write in a wrapping text mode and the address counter it
+    /// belongs to
+    text_cursor: Option<(u8, (u8, u8))>,
+
+    /// CGRAM slots handed out as glyph handles
+    pub(crate) cgram: CgramAllocator,
+
+    /// Fallback glyphs loaded into CGRAM
+    pub(crate) glyph_cache: Option<GlyphCache>,
+
+    /// Copy of the icons written to SEGRAM
+    pub(crate) icons: Icons,
+}
+
+impl State {
+    pub(crate) fn new(data_length: DataLength) -> Self {
+        Self {
+            config: Config::default(),
+            data_length,
+            queue: Vec::new(),
+            bank: Bank::RE0_IS0, // Power-on state
+            address_counter: AddressCounter::Unknown,
+            text_cursor: None,
+            cgram: CgramAllocator::new(),
+            glyph_cache: None,
+            icons: Icons::new(),
+        }
+    }
+
+    /// Take note that the queued frames were sent, or that they were lost to a bus error
+    pub(crate) fn sent(&mut self, ok: bool) {
+        // Part of the frames may have gone out before the error
+        self.bank = if ok { self.pending_bank() } else { Bank::ANY };
+        self.queue.clear();
+    }
+
+    /// Drop the queued frames and return to the power-on bank after the controller was reset
+    pub(crate) fn reset(&mut self) {
+        self.queue.clear();
+        self.bank = Bank::RE0_IS0;
+        self.address_counter = AddressCounter::Unknown;
+    }
+
+    /// Take note of data written without tracking it, which moves a DDRAM address counter by an
+    /// unknown number of cells
+    pub(crate) fn untracked_data(&mut self) {
+        if let AddressCounter::Ddram(_) = self.address_counter {
+            self.address_counter = AddressCounter::Unknown;
+        }
+    }
+
+    /// DDRAM address the address counter points to, if known
+    pub(crate) fn ddram_address(&self) -> Option<u8> {
+        match self.address_counter {
+            AddressCounter::Ddram(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// Frames that still fit into the queue
+    pub(crate) fn room(&self) -> usize {
+        self.queue.capacity() - self.queue.len()
+    }
+
+    pub(crate) fn queue_command(&mut self, command: u8) {
+        self.queue_frame(Frame::Command(command));
+    }
+
+    /// Queue an instruction byte the driver cannot follow, so it loses track of the address
+    /// counter
+    pub(crate) fn queue_raw_command(&mut self, command: u8) {
+        self.address_counter = AddressCounter::Unknown;
+        self.queue_command(command);
+    }
+
+    /// Queue an instruction, preceded by the function sets needed to reach its RE/IS bank
+    pub(crate) fn queue_instruction(&mut self, instruction: Instruction) {
+        self.address_counter = self.address_counter.after(instruction);
+        if matches!(
+            instruction,
+            Instruction::ClearDisplay | Instruction::ReturnHome | Instruction::SetDdramAddress(_)
+        ) {
+            self.text_cursor = None;
+        }
+        if instruction == Instruction::ClearDisplay
+            && let Some(cache) = &mut self.glyph_cache
+        {
+            cache.cleared();
+        }
+
+        self.select_bank(instruction.bank());
+        self.queue_command(instruction.cmd());
+        if let Some(parameter) = instruction.parameter() {
+            self.queue_data(&[parameter]);
+        }
+    }
+
+    pub(crate) fn queue_data(&mut self, data: &[u8]) {
+        for byte in data {
+            self.queue_frame(Frame::Data(*byte));
+        }
+    }
+
+    fn queue_frame(&mut self, frame: Frame) {
+        // Every operation sends its frames before the next one starts, see QUEUE_LEN
+        self.queue.push(frame).unwrap();
+    }
+
+    /// Bank the controller ends up in once the queued frames are sent
+    fn pending_bank(&self) -> Bank {
+        self.queue
+            .iter()
+            .fold(self.bank, |bank, frame| match frame {
+                Frame::Command(command) if command & FUNCTION_SET_MASK == FUNCTION_SET => {
+                    bank.after_function_set(*command)
+                }
+                _ => bank,
+            })
+    }
+
+    /// Queue the function sets needed to reach `required`, leaving out the ones whose bits are
+    /// already in place
+    pub(crate) fn select_bank(&mut self, required: Bank) {
+        let current = self.pending_bank();
+        if current.satisfies(required) {
+            return;
+        }
+
+        let settings = self.config.display_settings;
+        // IS can only be written with RE = 0
+        if required.re == Some(false) || required.is.is_some_and(|is| current.is != Some(is)) {
+            let cmd = match required.is.or(current.is) {
+                Some(true) => settings.cmd_re0_is1(),
+                _ => settings.cmd_re0_is0(),
+            };
+            self.function_set(cmd);
+        }
+        if required.re == Some(true) {
+            self.function_set(settings.cmd_re1_is0());
+        }
+    }
+
+    /// Queue a function set, clearing the DL bit when the bus is only 4 bits wide
+    pub(crate) fn function_set(&mut self, cmd: u8) {
+        match self.data_length {
+            DataLength::EightBit => self.queue_command(cmd | FUNCTION_SET_DL),
+            DataLength::FourBit => self.queue_command(cmd & !FUNCTION_SET_DL),
+        }
+    }
+
+    fn extended_function(&self) -> ExtendedFunctionSet {
+        ExtendedFunctionSet::new(
+            self.config.font_width,
+            self.config.bw_inversion,
+            self.config.four_line_enabled,
+        )
+    }
+
+    /// Double height format along with the BS1 bias bit and the choice between display shift and
+    /// dot scroll. The format does not matter while DH is cleared.
+    fn double_height_bias(&self) -> DoubleHeightBiasDisplayShift {
+        let mode = self.config.double_height.unwrap_or(DoubleHeight::Lines3Top);
+        let dot_scroll = self.config.scroll_quantity.is_some();
+        DoubleHeightBiasDisplayShift::new(mode, dot_scroll, self.config.bias.bs1())
+    }
+
+    /// Oscillator frequency along with the BS0 bias bit
+    fn oscillator(&self) -> OscillatorSettings {
+        OscillatorSettings::new(self.config.osc_freq_cmd.freq, self.config.bias.bs0())
+    }
+
+    /// Take over `config` and queue everything up to the power-up of the analog circuits
+    pub(crate) fn init(&mut self, config: Config) {
+        self.config = config;
+        self.config.display_settings.double_height = config.double_height.is_some();
+
+        // Line number and DH bits, also brings the bank tracking in line with the controller
+        self.function_set(self.config.display_settings.cmd_re0_is0());
+        self.queue_instruction(Instruction::ExtendedFunctionSet(self.extended_function()));
+        self.queue_instruction(Instruction::ViewMode(config.view_mode, config.seg_comm));
+        self.queue_instruction(Instruction::DoubleHeightBiasDotShift(
+            self.double_height_bias(),
+        ));
+        self.queue_instruction(Instruction::RomSelection(config.charset));
+        if let Some(tc) = config.temperature_coefficient {
+            self.queue_instruction(Instruction::TemperatureCoefficient(tc));
+        }
+        if let Some(dots) = config.scroll_quantity {
+            self.queue_instruction(Instruction::SetScrollQuantity(dots));
+        }
+        if let Some(lines) = config.shift_lines {
+            self.queue_instruction(Instruction::ShiftScrollEnable(lines));
+        }
+        self.queue_instruction(Instruction::Oscillator(self.oscillator()));
+        self.queue_instruction(Instruction::FollowerControl(config.follower));
+        self.queue_instruction(Instruction::PowerIconContrast(config.power_icon_contrast));
+        self.queue_instruction(Instruction::Contrast(config.power_icon_contrast));
+    }
+
+    /// Queue the entry mode and display control of the configuration
+    pub(crate) fn init_display(&mut self) {
+        self.queue_instruction(Instruction::EntryMode(self.config.entry_mode));
+        self.queue_instruction(Instruction::DisplayControl(self.config.display_control));
+    }
+
+    /// Queue the glyphs held by handles for CGRAM and forget the fallback glyphs, since a reset
+    /// leaves CGRAM undefined
+    pub(crate) fn restore_cgram(&mut self) {
+        if let Some(cache) = &mut self.glyph_cache {
+            cache.reset();
+        }
+        let cgram = self.cgram.clone();
+        for (slot, glyph) in cgram.glyphs() {
+            self.queue_instruction(Instruction::SetCgramAddress(slot << 3));
+            self.queue_data(glyph);
+        }
+    }
+
+    /// Queue the copy of the icons for SEGRAM, which a reset leaves undefined. Nothing is queued
+    /// while no icon is set.
+    pub(crate) fn restore_segram(&mut self) {
+        let icons = *self.icons.data();
+        if icons.iter().any(|&byte| byte != 0) {
+            self.queue_instruction(Instruction::SetSegramAddress(0));
+            self.queue_data(&icons);
+        }
+    }
+
+    /// Rows of the current layout
+    pub(crate) fn rows(&self) -> u8 {
+        row_addresses(self.config.double_height).len() as u8
+    }
+
+    /// Visible cells whose fallback glyph was replaced since the last call
+    pub(crate) fn take_stale_cells(&mut self) -> StaleCells {
+        let stale = self.glyph_cache.as_mut().map_or(0, GlyphCache::take_stale);
+        let rows = row_addresses(self.config.double_height);
+        StaleCells::new(stale, ddram_offset(self.config.view_mode), rows)
+    }
+
+    /// Queue the DDRAM address for a 1-based row and column, `None` if there is no such cell
+    pub(crate) fn queue_locate(&mut self, row: u8, col: u8) -> Option<()> {
+        let address = ddram_address(&self.config, row, col)?;
+        self.queue_instruction(Instruction::SetDdramAddress(address));
+        Some(())
+    }
+
+    /// Move the address counter past `codes` and note which cells now show a fallback glyph
+    pub(crate) fn track_ddram_write(&mut self, codes: &[u8]) {
+        let AddressCounter::Ddram(mut address) = self.address_counter else {
+            return;
+        };
+        for &code in codes {
+            if let Some(cache) = &mut self.glyph_cache {
+                cache.written(address, code);
+            }
+            address = next_ddram_address(address, self.config.entry_mode.direction);
+        }
+        self.address_counter = AddressCounter::Ddram(address);
+    }
+
+    /// Move the address counter past `count` characters read from DDRAM
+    pub(crate) fn track_ddram_read(&mut self, count: usize) {
+        let AddressCounter::Ddram(mut address) = self.address_counter else {
+            return;
+        };
+        for _ in 0..count {
+            address = next_ddram_address(address, self.config.entry_mode.direction);
+        }
+        self.address_counter = AddressCounter::Ddram(address);
+    }
+
+    /// Row and column text in a wrapping text mode continues at, once the address counter is
+    /// known to be at DDRAM `address`. Text written from a hidden address, or while the address
+    /// counter points to CGRAM or SEGRAM (`None`), starts over in the first row.
+    pub(crate) fn text_start(&mut self, address: Option<u8>) -> (u8, u8) {
+        if let Some((cursor_address, position)) = self.text_cursor
+            && Some(cursor_address) == address
+        {
+            return position;
+        }
+        let position = address.and_then(|address| text_position(&self.config, address));
+        position.unwrap_or_else(|| {
+            self.queue_locate(1, 1);
+            (1, 1)
+        })
+    }
+
+    /// Remember where text written in a wrapping text mode continues
+    pub(crate) fn text_written(&mut self, position: (u8, u8)) {
+        self.text_cursor = self.ddram_address().map(|address| (address, position));
+    }
+
+    /// Slot of the fallback glyph for `c`, `None` to use the fallback character. Without a known
+    /// address counter no glyph is uploaded, since it could not be restored afterwards.
+    pub(crate) fn fallback_slot(&mut self, c: char) -> Option<GlyphSlot> {
+        self.ddram_address()?;
+        self.glyph_cache.as_mut()?.slot(c, self.cgram.taken())
+    }
+
+    /// Queue a glyph upload from [`State::fallback_slot`] and point the address counter back to
+    /// DDRAM
+    pub(crate) fn queue_glyph_upload(&mut self, slot: u8, glyph: &Glyph) {
+        self.queue_outside_ddram(Instruction::SetCgramAddress(slot << 3), glyph);
+    }
+
+    /// Take a CGRAM slot showing `glyph` and queue its upload if the slot does not show it yet.
+    /// `None` if all slots hold other glyphs.
+    pub(crate) fn alloc_glyph(&mut self, glyph: &Glyph) -> Option<GlyphHandle> {
+        let fallback = self.glyph_cache.as_ref().map_or(0, GlyphCache::loaded);
+        let (handle, upload) = self.cgram.alloc(glyph, fallback)?;
+
+        if upload {
+            if let Some(cache) = &mut self.glyph_cache {
+                cache.evict(handle.code());
+            }
+            self.queue_glyph_upload(handle.code(), glyph);
+        }
+        Some(handle)
+    }
+
+    /// Queue a bitmap for CGRAM `location` (0-7), `None` if it does not fit
+    pub(crate) fn create_custom_char(&mut self, location: u8, charmap: &[u8]) -> Option<()> {
+        let loc = location & 0x07; // Only 8 locations (0-7)
+        if loc as usize * 8 + charmap.len() > CGRAM_SIZE {
+            return None;
+        }
+
+        if let Some(cache) = &mut self.glyph_cache {
+            let glyphs = charmap.len().div_ceil(8) as u8;
+            (loc..loc + glyphs).for_each(|slot| cache.evict(slot));
+        }
+
+        self.queue_instruction(Instruction::SetCgramAddress(loc << 3));
+        self.queue_data(charmap);
+        Some(())
+    }
+
+    /// Queue the CGRAM address to read `len` bytes from at `location` (0-7), `None` if they do
+    /// not fit
+    pub(crate) fn read_custom_char(&mut self, location: u8, len: usize) -> Option<()> {
+        let loc = location & 0x07; // Only 8 locations (0-7)
+        if loc as usize * 8 + len > CGRAM_SIZE {
+            return None;
+        }
+
+        self.queue_instruction(Instruction::SetCgramAddress(loc << 3));
+        Some(())
+    }
+
+    /// Queue the DDRAM address to read `len` characters from, `None` if they do not fit into the
+    /// row
+    pub(crate) fn read_ddram(&mut self, row: u8, col: u8, len: usize) -> Option<()> {
+        if col as usize + len > 17 {
+            return None;
+        }
+        self.queue_locate(row, col)
+    }
+
+    /// Queue SEGRAM data from `address` on and point the address counter back to DDRAM
+    pub(crate) fn queue_segram(&mut self, address: u8, data: &[u8]) {
+        self.queue_outside_ddram(Instruction::SetSegramAddress(address), data);
+    }
+
+    /// Queue `data` for the CGRAM or SEGRAM address set by `set_address`, then point the address
+    /// counter back to the DDRAM address it had, if known
+    fn queue_outside_ddram(&mut self, set_address: Instruction, data: &[u8]) {
+        let (ddram_address, text_cursor) = (self.ddram_address(), self.text_cursor);
+        self.queue_instruction(set_address);
+        self.queue_data(data);
+        if let Some(ddram_address) = ddram_address {
+            self.queue_instruction(Instruction::SetDdramAddress(ddram_address));
+        }
+        self.text_cursor = text_cursor;
+    }
+
+    pub(crate) fn set_contrast(&mut self, contrast: u8) -> Option<()> {
+        if contrast > 63 {
+            return None;
+        }
+
+        self.config.power_icon_contrast.contrast = contrast;
+        let settings = self.config.power_icon_contrast;
+        self.queue_instruction(Instruction::PowerIconContrast(settings));
+        self.queue_instruction(Instruction::Contrast(settings));
+        Some(())
+    }
+
+    pub(crate) fn set_booster(&mut self, booster: bool) {
+        self.config.power_icon_contrast.booster = booster;
+        self.queue_instruction(Instruction::PowerIconContrast(
+            self.config.power_icon_contrast,
+        ));
+    }
+
+    pub(crate) fn set_follower(&mut self, follower: FollowerControl) {
+        self.config.follower = follower;
+        self.queue_instruction(Instruction::FollowerControl(follower));
+    }
+
+    pub(crate) fn set_temperature_coefficient(&mut self, tc: u8) -> Option<()> {
+        if tc > 7 {
+            return None;
+        }
+
+        self.config.temperature_coefficient = Some(tc);
+        self.queue_instruction(Instruction::TemperatureCoefficient(tc));
+        Some(())
+    }
+
+    pub(crate) fn enable_line_shift(&mut self, lines: ShiftLines) {
+        self.config.shift_lines = Some(lines);
+        self.queue_instruction(Instruction::ShiftScrollEnable(lines));
+    }
+
+    pub(crate) fn set_scroll_quantity(&mut self, dots: u8) -> Option<()> {
+        if dots > MAX_SCROLL_DOTS {
+            return None;
+        }
+
+        self.config.scroll_quantity = Some(dots);
+        self.queue_instruction(Instruction::DoubleHeightBiasDotShift(
+            self.double_height_bias(),
+        ));
+        self.queue_instruction(Instruction::SetScrollQuantity(dots));
+        Some(())
+    }
+
+    pub(crate) fn set_inverted(&mut self, inverted: bool) {
+        self.config.display_settings.reverse_enable = inverted;
+        self.function_set(self.config.display_settings.cmd_re1_is0());
+    }
+
+    pub(crate) fn set_data_blink(&mut self, blink: bool) {
+        self.config.display_settings.data_blink_enable = blink;
+        self.function_set(self.config.display_settings.cmd_re1_is0());
+    }
+
+    pub(crate) fn set_bias(&mut self, bias: Bias) {
+        self.config.bias = bias;
+        self.queue_instruction(Instruction::DoubleHeightBiasDotShift(
+            self.double_height_bias(),
+        ));
+        self.queue_instruction(Instruction::Oscillator(self.oscillator()));
+    }
+
+    pub(crate) fn set_icons_enabled(&mut self, enabled: bool) {
+        self.config.power_icon_contrast.icon = enabled;
+        self.queue_instruction(Instruction::PowerIconContrast(
+            self.config.power_icon_contrast,
+        ));
+    }
+
+    pub(crate) fn set_icon(&mut self, index: u8, on: bool) -> Option<()> {
+        let (address, byte) = self.icons.set(index, on)?;
+        self.queue_segram(address, &[byte]);
+        Some(())
+    }
+
+    pub(crate) fn set_icon_blink(&mut self, index: u8, blink: bool) -> Option<()> {
+        let (address, byte) = self.icons.set_blink(index, blink)?;
+        self.queue_segram(address, &[byte]);
+        Some(())
+    }
+
+    pub(crate) fn write_icons(&mut self, data: &[u8; SEGRAM_SIZE]) {
+        self.icons.load(data);
+        self.queue_segram(0, data);
+    }
+
+    pub(crate) fn set_rom(&mut self, rom: Rom) {
+        self.config.charset = rom;
+        self.queue_instruction(Instruction::RomSelection(rom));
+    }
+
+    /// Queue everything entering power-down mode
+    pub(crate) fn sleep(&mut self) {
+        let mut display = self.config.display_control;
+        display.remove(DisplayConfig::DISPLAY_ON);
+        let booster_off = PowerIconContrast {
+            booster: false,
+            ..self.config.power_icon_contrast
+        };
+        let follower_off = FollowerControl {
+            d_on: false,
+            ..self.config.follower
+        };
+
+        self.queue_instruction(Instruction::DisplayControl(display));
+        self.queue_instruction(Instruction::PowerIconContrast(booster_off));
+        self.queue_instruction(Instruction::FollowerControl(follower_off));
+        self.queue_instruction(Instruction::PowerDown(true));
+    }
+
+    /// Queue everything up to the power-up of the analog circuits when leaving power-down mode
+    pub(crate) fn wake(&mut self) {
+        let config = self.config;
+        self.queue_instruction(Instruction::PowerDown(false));
+        self.queue_instruction(Instruction::FollowerControl(config.follower));
+        self.queue_instruction(Instruction::PowerIconContrast(config.power_icon_contrast));
+        self.queue_instruction(Instruction::Contrast(config.power_icon_contrast));
+    }
+
+    pub(crate) fn set_display(&mut self, flags: DisplayConfig) {
+        self.config.display_control = flags;
+        self.queue_instruction(Instruction::DisplayControl(flags));
+    }
+
+    /// Queue a display shift, switching dot scroll off first since the shift only works without
+    pub(crate) fn shift_display(&mut self, direction: HorizontalDir) {
+        if self.config.scroll_quantity.take().is_some() {
+            self.queue_instruction(Instruction::DoubleHeightBiasDotShift(
+                self.double_height_bias(),
+            ));
+        }
+        self.queue_instruction(Instruction::Shift(ShiftSettings::new(
+            direction,
+            ShiftType::Display,
+        )));
+    }
+
+    pub(crate) fn set_entry_mode(&mut self, mode: EntryModeSettings) {
+        self.config.entry_mode = mode;
+        self.queue_instruction(Instruction::EntryMode(mode));
+    }
+
+    pub(crate) fn set_view_mode(&mut self, mode: ViewMode) {
+        self.config.view_mode = mode;
+        self.queue_instruction(Instruction::ViewMode(mode, self.config.seg_comm));
+    }
+
+    /// Change the display control flags of the configuration and queue them
+    pub(crate) fn update_display(&mut self, update: impl FnOnce(&mut DisplayConfig)) {
+        update(&mut self.config.display_control);
+        self.queue_instruction(Instruction::DisplayControl(self.config.display_control));
+    }
+
+    pub(crate) fn set_cursor_inverted(&mut self, inverted: bool) {
+        self.config.bw_inversion = inverted;
+        self.extended_function_set();
+    }
+
+    pub(crate) fn extended_function_set(&mut self) {
+        self.queue_instruction(Instruction::ExtendedFunctionSet(self.extended_function()));
+    }
+
+    /// `None` if the display is not in 2/4 line mode, which double height needs
+    pub(crate) fn set_double_height(&mut self, mode: Option<DoubleHeight>) -> Option<()> {
+        let line_mode = self.config.display_settings.line_number_control;
+        if mode.is_some() && line_mode != LineDisplayMode::TwoOrFourLines {
+            return None;
+        }
+
+        self.config.double_height = mode;
+        // Kept in the display settings so later bank switches do not clear it again
+        self.config.display_settings.double_height = mode.is_some();
+        self.queue_instruction(Instruction::DoubleHeightBiasDotShift(
+            self.double_height_bias(),
+        ));
+        self.function_set(self.config.display_settings.cmd_re0_is0());
+        Some(())
+    }
+
+    /// Queue `chars` (1-16) blanks from a 1-based row and column on and return to the first one,
+    /// `None` if they do not fit into the row
+    pub(crate) fn clear_chars(&mut self, row: u8, col: u8, chars: u8) -> Option<()> {
+        if col == 0 || col > 16 || chars == 0 || chars > 16 {
+            return None;
+        }
+
+        let blanks = &[b' '; 16][..chars as usize];
+        self.queue_locate(row, col)?;
+        self.queue_data(blanks);
+        self.track_ddram_write(blanks);
+        self.queue_locate(row, col)
+    }
+}
@@ -0,0 +1,431 @@
+use crate::cgram::blinking;
+use crate::commands::{
+    Bias, DisplayConfig, DoubleHeight, EntryModeSettings, HorizontalDir, Rom, ShiftLines, ViewMode,
+};
+use crate::compensation::{ContrastCurve, TemperatureCompensation};
+use crate::config::{Config, TextMode};
+use crate::interface::{Frame, I2cInterface};
+use crate::marquee::{Marquee, MarqueeMode};
+use crate::segram::SEGRAM_SIZE;
+use crate::ssd18030_async::{LcdAsync, SSD18030Async};
+use crate::ssd18030_i2c::{Lcd, LcdError, ProbeError, SSD18030};
+use crate::tests::mock::{MockI2c, MockResetPin, NoDelay, block_on};
+use heapless::Vec;
+
+type Blocking = SSD18030<I2cInterface<MockI2c>, NoDelay>;
+type Async = SSD18030Async<MockI2c, NoDelay>;
+
+fn drivers() -> (Blocking, Async) {
+    (
+        SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay),
+        SSD18030Async::new_i2c(MockI2c::default(), 0x3D, NoDelay),
+    )
+}
+
+/// Instruction and data bytes the controller receives from the recorded writes
+fn frames(writes: &[Vec<u8, 128>]) -> Vec<Frame, 1024> {
+    let mut frames = Vec::new();
+    for write in writes {
+        let mut bytes = write.iter();
+        while let Some(&control) = bytes.next() {
+            let frame = |byte| match control & 0x40 {
+                0 => Frame::Command(byte),
+                _ => Frame::Data(byte),
+            };
+            if control & 0x80 != 0 {
+                if let Some(&byte) = bytes.next() {
+                    frames.push(frame(byte)).unwrap();
+                }
+            } else {
+                for &byte in bytes.by_ref() {
+                    frames.push(frame(byte)).unwrap();
+                }
+            }
+        }
+    }
+    frames
+}
+
+/// Check that both drivers made the same I2C writes and return the frames they carried
+fn same_writes(blocking: Blocking, asynchronous: Async) -> Vec<Frame, 1024> {
+    let (i2c, _, _) = blocking.release();
+    let writes = i2c.release().writes;
+    let (i2c, _, _) = asynchronous.release();
+    assert_eq!(writes, i2c.writes);
+    frames(&writes)
+}
+
+#[test]
+fn test_async_locate_write() {
+    let mut lcd = SSD18030Async::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    block_on(async {
+        lcd.locate(2, 3).await.unwrap();
+        lcd.write("ok").await.unwrap();
+    });
+
+    let (i2c, _, _) = lcd.release();
+    assert_eq!(i2c.writes[0], [0x00, 0x84 + 0x22]);
+    assert_eq!(i2c.writes[1], [0x40, b'o', b'k']);
+}
+
+#[test]
+fn test_async_rejects_invalid_row() {
+    let mut lcd = SSD18030Async::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    assert!(block_on(lcd.locate(5, 1)).is_err());
+}
+
+#[test]
+fn test_async_init_and_settings() {
+    let config = Config::builder()
+        .view_mode(ViewMode::Bottom)
+        .temperature_coefficient(Some(2))
+        .build()
+        .unwrap();
+    let (mut lcd, mut lcd_async) = drivers();
+
+    lcd.init(config).unwrap();
+    lcd.set_contrast(40).unwrap();
+    lcd.set_bias(Bias::OneFifth).unwrap();
+    lcd.set_booster(false).unwrap();
+    lcd.set_temperature_coefficient(5).unwrap();
+    lcd.set_rom(Rom::C).unwrap();
+    lcd.write("25°C").unwrap();
+    lcd.set_view_mode(ViewMode::Top).unwrap();
+    lcd.set_entry_mode(EntryModeSettings::new(HorizontalDir::RightToLeft, false))
+        .unwrap();
+    lcd.set_display(DisplayConfig::DISPLAY_ON).unwrap();
+    lcd.extended_function_set().unwrap();
+    lcd.clear_line(2).unwrap();
+    lcd.clear_chars((3, 4), 5).unwrap();
+    lcd.write_raw(&[0xDF, 0x00]).unwrap();
+
+    block_on(async {
+        lcd_async.init(config).await.unwrap();
+        lcd_async.set_contrast(40).await.unwrap();
+        lcd_async.set_bias(Bias::OneFifth).await.unwrap();
+        lcd_async.set_booster(false).await.unwrap();
+        lcd_async.set_temperature_coefficient(5).await.unwrap();
+        lcd_async.set_rom(Rom::C).await.unwrap();
+        lcd_async.write("25°C").await.unwrap();
+        lcd_async.set_view_mode(ViewMode::Top).await.unwrap();
+        lcd_async
+            .set_entry_mode(EntryModeSettings::new(HorizontalDir::RightToLeft, false))
+            .await
+            .unwrap();
+        lcd_async
+            .set_display(DisplayConfig::DISPLAY_ON)
+            .await
+            .unwrap();
+        lcd_async.extended_function_set().await.unwrap();
+        lcd_async.clear_line(2).await.unwrap();
+        lcd_async.clear_chars((3, 4), 5).await.unwrap();
+        lcd_async.write_raw(&[0xDF, 0x00]).await.unwrap();
+    });
+
+    assert_eq!(lcd.config(), lcd_async.config());
+    let frames = same_writes(lcd, lcd_async);
+    // Line number and DH bits open the init sequence
+    assert_eq!(frames[0], Frame::Command(0x38));
+}
+
+#[test]
+fn test_async_sleep_shift_and_double_height() {
+    let (mut lcd, mut lcd_async) = drivers();
+
+    lcd.sleep().unwrap();
+    lcd.wake().unwrap();
+    lcd.enable_line_shift(ShiftLines::LINE1 | ShiftLines::LINE3)
+        .unwrap();
+    lcd.set_scroll_quantity(12).unwrap();
+    lcd.shift_display(HorizontalDir::RightToLeft).unwrap();
+    lcd.locate(1, 1).unwrap();
+    lcd.shift_cursor(HorizontalDir::LeftToRight).unwrap();
+    lcd.set_double_height(Some(DoubleHeight::Lines3Top))
+        .unwrap();
+    lcd.locate(2, 1).unwrap();
+    lcd.set_double_height(None).unwrap();
+    let cursor = lcd.cursor().unwrap();
+
+    let cursor_async = block_on(async {
+        lcd_async.sleep().await.unwrap();
+        lcd_async.wake().await.unwrap();
+        lcd_async
+            .enable_line_shift(ShiftLines::LINE1 | ShiftLines::LINE3)
+            .await
+            .unwrap();
+        lcd_async.set_scroll_quantity(12).await.unwrap();
+        lcd_async
+            .shift_display(HorizontalDir::RightToLeft)
+            .await
+            .unwrap();
+        lcd_async.locate(1, 1).await.unwrap();
+        lcd_async
+            .shift_cursor(HorizontalDir::LeftToRight)
+            .await
+            .unwrap();
+        lcd_async
+            .set_double_height(Some(DoubleHeight::Lines3Top))
+            .await
+            .unwrap();
+        lcd_async.locate(2, 1).await.unwrap();
+        lcd_async.set_double_height(None).await.unwrap();
+        lcd_async.cursor().await.unwrap()
+    });
+
+    assert_eq!(cursor, cursor_async);
+    assert_eq!(lcd.config(), lcd_async.config());
+    same_writes(lcd, lcd_async);
+}
+
+#[test]
+fn test_async_glyphs_and_blink() {
+    static GLYPHS: [(char, [u8; 8]); 1] = [('₀', [1, 2, 3, 4, 5, 6, 7, 8])];
+    let bell = blinking(&[0x04; 8]);
+    let (mut lcd, mut lcd_async) = drivers();
+
+    lcd.enable_fallback_glyphs(&GLYPHS);
+    lcd.locate(1, 1).unwrap();
+    lcd.write("a₀b₀").unwrap();
+    lcd.set_data_blink(true).unwrap();
+    lcd.set_inverted(true).unwrap();
+    let glyph = lcd.alloc_glyph(&bell).unwrap();
+    lcd.write_special_char(&glyph).unwrap();
+    lcd.create_custom_char(0, &[0; 8]).unwrap();
+    let stale = lcd.take_stale_cells().count();
+    lcd.release_glyph(glyph);
+
+    lcd_async.enable_fallback_glyphs(&GLYPHS);
+    let stale_async = block_on(async {
+        lcd_async.locate(1, 1).await.unwrap();
+        lcd_async.write("a₀b₀").await.unwrap();
+        lcd_async.set_data_blink(true).await.unwrap();
+        lcd_async.set_inverted(true).await.unwrap();
+        let glyph = lcd_async.alloc_glyph(&bell).await.unwrap();
+        lcd_async.write_special_char(&glyph).await.unwrap();
+        lcd_async.create_custom_char(0, &[0; 8]).await.unwrap();
+        lcd_async.release_glyph(glyph);
+        lcd_async.take_stale_cells().count()
+    });
+
+    assert_eq!((stale, stale_async), (2, 2));
+    let frames = same_writes(lcd, lcd_async);
+    assert!(frames.contains(&Frame::Data(0x84)));
+}
+
+#[test]
+fn test_async_icons_and_cursor() {
+    let (mut lcd, mut lcd_async) = drivers();
+
+    lcd.locate(3, 2).unwrap();
+    lcd.set_icons_enabled(true).unwrap();
+    lcd.set_icon(7, true).unwrap();
+    lcd.set_icon_blink(9, true).unwrap();
+    lcd.write_icons(&[0x1F; SEGRAM_SIZE]).unwrap();
+    lcd.set_cursor(true, false).unwrap();
+    lcd.set_cursor_inverted(false).unwrap();
+    let cursor = lcd.cursor().unwrap();
+    let invalid = lcd.set_icon(80, true);
+
+    let (cursor_async, invalid_async) = block_on(async {
+        lcd_async.locate(3, 2).await.unwrap();
+        lcd_async.set_icons_enabled(true).await.unwrap();
+        lcd_async.set_icon(7, true).await.unwrap();
+        lcd_async.set_icon_blink(9, true).await.unwrap();
+        lcd_async.write_icons(&[0x1F; SEGRAM_SIZE]).await.unwrap();
+        lcd_async.set_cursor(true, false).await.unwrap();
+        lcd_async.set_cursor_inverted(false).await.unwrap();
+        let cursor = lcd_async.cursor().await.unwrap();
+        (cursor, lcd_async.set_icon(80, true).await)
+    });
+
+    assert_eq!(cursor, Some((3, 2)));
+    assert_eq!(cursor_async, Some((3, 2)));
+    assert!(matches!(invalid, Err(LcdError::InvalidInputData)));
+    assert!(matches!(invalid_async, Err(LcdError::InvalidInputData)));
+    assert_eq!(lcd.icons(), lcd_async.icons());
+    same_writes(lcd, lcd_async);
+}
+
+#[test]
+fn test_async_text_modes() {
+    let (mut lcd, mut lcd_async) = drivers();
+
+    lcd.set_text_mode(TextMode::StopAtEnd);
+    lcd.clear().unwrap();
+    lcd.write("hello\nworld, this wraps").unwrap();
+    lcd.locate(4, 15).unwrap();
+    lcd.write("abc").unwrap();
+    lcd.write("QQ").unwrap();
+    lcd.home().unwrap();
+    lcd.write("again\r").unwrap();
+    lcd.set_text_mode(TextMode::WrapToTop);
+    lcd.locate(4, 16).unwrap();
+    lcd.write("xy").unwrap();
+    let cursor = lcd.cursor().unwrap();
+
+    lcd_async.set_text_mode(TextMode::StopAtEnd);
+    let cursor_async = block_on(async {
+        lcd_async.clear().await.unwrap();
+        lcd_async.write("hello\nworld, this wraps").await.unwrap();
+        lcd_async.locate(4, 15).await.unwrap();
+        lcd_async.write("abc").await.unwrap();
+        lcd_async.write("QQ").await.unwrap();
+        lcd_async.home().await.unwrap();
+        lcd_async.write("again\r").await.unwrap();
+        lcd_async.set_text_mode(TextMode::WrapToTop);
+        lcd_async.locate(4, 16).await.unwrap();
+        lcd_async.write("xy").await.unwrap();
+        lcd_async.cursor().await.unwrap()
+    });
+
+    assert_eq!(cursor, Some((1, 2)));
+    assert_eq!(cursor_async, Some((1, 2)));
+    let frames = same_writes(lcd, lcd_async);
+    assert!(!frames.contains(&Frame::Data(b'Q')));
+}
+
+#[test]
+fn test_async_compensation_and_marquee() {
+    let curve = ContrastCurve::Table(&[(-20, 50), (60, 34)]);
+    let mut compensation = TemperatureCompensation::new(curve, 3);
+    let mut compensation_async = compensation;
+    let mut marquee = Marquee::new(2, "0123456789abcdefghi").mode(MarqueeMode::Bounce);
+    let mut marquee_async = marquee.clone();
+    let mut software = Marquee::new(3, "The quick brown fox jumps over");
+    let mut software_async = software.clone();
+    let (mut lcd, mut lcd_async) = drivers();
+
+    lcd.compensate_contrast(&mut compensation, 0).unwrap();
+    lcd.compensate_contrast(&mut compensation, 2).unwrap();
+    lcd.compensate_contrast(&mut compensation, 5).unwrap();
+    marquee.start(&mut lcd).unwrap();
+    software.start(&mut lcd).unwrap();
+    for _ in 0..4 {
+        marquee.tick(&mut lcd).unwrap();
+        software.tick(&mut lcd).unwrap();
+    }
+
+    block_on(async {
+        let lcd = &mut lcd_async;
+        lcd.compensate_contrast(&mut compensation_async, 0)
+            .await
+            .unwrap();
+        lcd.compensate_contrast(&mut compensation_async, 2)
+            .await
+            .unwrap();
+        lcd.compensate_contrast(&mut compensation_async, 5)
+            .await
+            .unwrap();
+        marquee_async.start_async(lcd).await.unwrap();
+        software_async.start_async(lcd).await.unwrap();
+        for _ in 0..4 {
+            marquee_async.tick_async(lcd).await.unwrap();
+            software_async.tick_async(lcd).await.unwrap();
+        }
+    });
+
+    assert_eq!(lcd.config(), lcd_async.config());
+    same_writes(lcd, lcd_async);
+}
+
+#[test]
+fn test_async_batches_instructions() {
+    let mut lcd = SSD18030Async::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    block_on(async {
+        lcd.set_contrast(0x31).await.unwrap();
+        {
+            let mut batch = lcd.batch();
+            batch.command(0x01).await.unwrap();
+            batch.command(0x84).await.unwrap();
+            batch.data(b"ab").await.unwrap();
+            batch.send().await.unwrap();
+        }
+        // Dropped without sending
+        lcd.batch().command(0x01).await.unwrap();
+    });
+
+    let (i2c, _, _) = lcd.release();
+    assert_eq!(i2c.writes.len(), 2);
+    assert_eq!(i2c.writes[0], [0x00, 0x39, 0x57, 0x71]);
+    assert_eq!(i2c.writes[1], [0x80, 0x01, 0x80, 0x84, 0x40, b'a', b'b']);
+}
+
+#[test]
+fn test_async_batch_past_queue_limit() {
+    let (mut lcd, mut lcd_async) = drivers();
+
+    lcd.batch().data(&[b'x'; 100]).unwrap();
+    block_on(async {
+        lcd_async.batch().data(&[b'x'; 100]).await.unwrap();
+    });
+
+    let frames = same_writes(lcd, lcd_async);
+    assert_eq!(frames.len(), 80);
+}
+
+#[test]
+fn test_async_reads() {
+    let i2c = || MockI2c {
+        reads: Vec::from_slice(b"\x05C1:\x1C\x14\x1C").unwrap(),
+        ..MockI2c::default()
+    };
+    let mut lcd = SSD18030::new_i2c(i2c(), 0x3D, NoDelay);
+    let mut lcd_async = SSD18030Async::new_i2c(i2c(), 0x3D, NoDelay);
+
+    let status = lcd.read_status().unwrap();
+    let mut text = [0u8; 3];
+    lcd.read_ddram(2, 1, &mut text).unwrap();
+    let mut charmap = [0u8; 3];
+    lcd.read_custom_char(1, &mut charmap).unwrap();
+    let invalid = lcd.read_ddram(1, 15, &mut text);
+
+    let (status_async, text_async, charmap_async, invalid_async) = block_on(async {
+        let status = lcd_async.read_status().await.unwrap();
+        let mut text = [0u8; 3];
+        lcd_async.read_ddram(2, 1, &mut text).await.unwrap();
+        let mut charmap = [0u8; 3];
+        lcd_async.read_custom_char(1, &mut charmap).await.unwrap();
+        let invalid = lcd_async.read_ddram(1, 15, &mut text).await;
+        (status, text, charmap, invalid)
+    });
+
+    assert_eq!(status.address, 0x05);
+    assert_eq!(status_async.address, 0x05);
+    assert_eq!((&text, &text_async), (b"C1:", b"C1:"));
+    assert_eq!(charmap, [0x1C, 0x14, 0x1C]);
+    assert_eq!(charmap_async, charmap);
+    assert!(matches!(invalid, Err(LcdError::InvalidInputData)));
+    assert!(matches!(invalid_async, Err(LcdError::InvalidInputData)));
+    same_writes(lcd, lcd_async);
+}
+
+#[test]
+fn test_async_probe_and_hard_reset() {
+    let mut i2c = MockI2c::default();
+    i2c.absent.push(0x3C).unwrap();
+    i2c.reads.push(0x10).unwrap();
+    assert!(matches!(
+        block_on(SSD18030Async::probe_i2c(i2c, NoDelay)),
+        Err(ProbeError::WrongChip {
+            address: 0x3D,
+            part_id: 0x10
+        })
+    ));
+
+    let mut i2c = MockI2c::default();
+    i2c.absent.push(0x3C).unwrap();
+    i2c.reads.push(0x1A).unwrap();
+    let mut lcd = block_on(SSD18030Async::probe_i2c(i2c, NoDelay))
+        .unwrap()
+        .with_reset_pin(MockResetPin::default());
+    block_on(async {
+        lcd.hard_reset().await.unwrap();
+        lcd.clear().await.unwrap();
+    });
+
+    let (i2c, _, reset) = lcd.release();
+    assert_eq!(reset.levels, [false, true]);
+    // Probing left RE set, the reset cleared it again
+    assert_eq!(i2c.writes[0], [0x00, 0x3A]);
+    assert_eq!(i2c.writes[2], [0x00, 0x01]);
+}
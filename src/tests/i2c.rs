@@ -0,0 +1,750 @@
+use crate::cgram::blinking;
+use crate::commands::{
+    Bias, DisplayConfig, DoubleHeight, FollowerControl, FontWidth, HorizontalDir, OscillatorFreq,
+    OscillatorSettings, Rab, Rom, SegCommControl, ShiftLines, VerticalDir, ViewMode,
+};
+use crate::compensation::{ContrastCurve, TemperatureCompensation};
+use crate::config::{Config, TextMode};
+use crate::instruction::Instruction;
+use crate::marquee::{Marquee, MarqueeMode};
+use crate::segram::SEGRAM_SIZE;
+use crate::ssd18030_i2c::{Lcd, LcdError, ProbeError, SSD18030};
+use crate::tests::mock::{MockI2c, MockResetPin, NoDelay};
+
+#[test]
+fn test_i2c_locate_write() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.locate(2, 3).unwrap();
+    lcd.write("ok").unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0], [0x00, 0x84 + 0x22]);
+    assert_eq!(writes[1], [0x40, b'o', b'k']);
+}
+
+#[test]
+fn test_i2c_rejects_invalid_position() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    assert!(matches!(lcd.locate(0, 1), Err(LcdError::InvalidInputData)));
+    assert!(matches!(lcd.locate(1, 17), Err(LcdError::InvalidInputData)));
+}
+
+#[test]
+fn test_i2c_batch_continuation() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.batch()
+        .command(0x01)
+        .unwrap()
+        .command(0x84)
+        .unwrap()
+        .data(b"ab")
+        .unwrap()
+        .send()
+        .unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0], [0x80, 0x01, 0x80, 0x84, 0x40, b'a', b'b']);
+}
+
+#[test]
+fn test_i2c_batch_past_queue_limit() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    {
+        let mut batch = lcd.batch();
+        batch.command(0x84).unwrap();
+        batch.data(&[b'x'; 100]).unwrap();
+        // Dropped without sending
+    }
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    // The command goes out to make room for the data, then the first 80 bytes of it
+    assert_eq!(writes[0], [0x00, 0x84]);
+    assert!(writes[1..].iter().all(|write| write[0] == 0x40));
+    let sent: usize = writes[1..].iter().map(|write| write.len() - 1).sum();
+    assert_eq!(sent, 80);
+}
+
+#[test]
+fn test_i2c_clear_line_single_write() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.clear_line(2).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0][..4], [0x80, 0xA4, 0xC0, b' ']);
+    assert_eq!(writes[0][34..], [0x00, 0xA4]);
+}
+
+#[test]
+fn test_i2c_dropped_batch_is_discarded() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.batch().command(0x01).unwrap();
+    lcd.home().unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    // Return home followed by the busy flag read
+    let writes = i2c.release().writes;
+    assert_eq!(writes.len(), 2);
+    assert_eq!(writes[0], [0x00, 0x02]);
+    assert_eq!(writes[1], [0x00]);
+}
+
+#[test]
+fn test_i2c_long_write() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    let text = "0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF";
+    lcd.write(text).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0][0], 0x40);
+    assert_eq!(&writes[0][1..], text.as_bytes());
+}
+
+#[test]
+fn test_i2c_custom_chars_fill_cgram() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.create_custom_char(0, &[0x1F; 64]).unwrap();
+    assert!(matches!(
+        lcd.create_custom_char(1, &[0x1F; 64]),
+        Err(LcdError::InvalidInputData)
+    ));
+}
+
+#[test]
+fn test_i2c_read_status() {
+    let mut i2c = MockI2c::default();
+    i2c.reads.push(0x85).unwrap();
+    let mut lcd = SSD18030::new_i2c(i2c, 0x3D, NoDelay);
+
+    let status = lcd.read_status().unwrap();
+    assert!(status.busy);
+    assert_eq!(status.address, 0x05);
+}
+
+#[test]
+fn test_i2c_clear_polls_busy_flag() {
+    let mut i2c = MockI2c::default();
+    i2c.reads.extend_from_slice(&[0x80, 0x80, 0x00]).unwrap();
+    let mut lcd = SSD18030::new_i2c(i2c, 0x3D, NoDelay);
+    lcd.clear().unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let i2c = i2c.release();
+    assert!(i2c.reads.is_empty());
+    assert_eq!(i2c.writes[0], [0x00, 0x01]);
+    assert_eq!(i2c.writes.len(), 4);
+}
+
+#[test]
+fn test_i2c_wait_ready_timeout() {
+    let mut i2c = MockI2c::default();
+    i2c.reads.extend_from_slice(&[0x80; 8]).unwrap();
+    let mut lcd = SSD18030::new_i2c(i2c, 0x3D, NoDelay);
+    assert!(matches!(lcd.wait_ready(20), Err(LcdError::Timeout)));
+}
+
+#[test]
+fn test_i2c_read_ddram() {
+    let mut i2c = MockI2c::default();
+    i2c.reads.extend_from_slice(b"C1:").unwrap();
+    let mut lcd = SSD18030::new_i2c(i2c, 0x3D, NoDelay);
+
+    let mut buffer = [0u8; 3];
+    lcd.read_ddram(2, 1, &mut buffer).unwrap();
+    assert_eq!(&buffer, b"C1:");
+    assert!(matches!(
+        lcd.read_ddram(1, 15, &mut buffer),
+        Err(LcdError::InvalidInputData)
+    ));
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0], [0x00, 0xA4]);
+    assert_eq!(writes[1], [0x40]);
+}
+
+#[test]
+fn test_i2c_read_custom_char() {
+    let mut i2c = MockI2c::default();
+    i2c.reads.extend_from_slice(&[0x1C, 0x14, 0x1C]).unwrap();
+    let mut lcd = SSD18030::new_i2c(i2c, 0x3D, NoDelay);
+
+    let mut charmap = [0u8; 8];
+    lcd.read_custom_char(1, &mut charmap).unwrap();
+    assert_eq!(charmap[..3], [0x1C, 0x14, 0x1C]);
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0], [0x00, 0x48]);
+}
+
+#[test]
+fn test_i2c_probe_second_address() {
+    let mut i2c = MockI2c::default();
+    i2c.absent.push(0x3C).unwrap();
+    i2c.reads.push(0x1A).unwrap();
+
+    assert!(SSD18030::probe_i2c(i2c, NoDelay).is_ok());
+}
+
+#[test]
+fn test_i2c_probe_wrong_chip() {
+    let mut i2c = MockI2c::default();
+    i2c.absent.push(0x3C).unwrap();
+    i2c.reads.push(0x10).unwrap();
+
+    assert!(matches!(
+        SSD18030::probe_i2c(i2c, NoDelay),
+        Err(ProbeError::WrongChip {
+            address: 0x3D,
+            part_id: 0x10
+        })
+    ));
+}
+
+#[test]
+fn test_i2c_probe_not_found() {
+    let mut i2c = MockI2c::default();
+    i2c.absent.extend_from_slice(&[0x3C, 0x3D]).unwrap();
+
+    assert!(matches!(
+        SSD18030::probe_i2c(i2c, NoDelay),
+        Err(ProbeError::NotFound)
+    ));
+}
+
+#[test]
+fn test_i2c_hard_reset() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay)
+        .with_reset_pin(MockResetPin::default());
+    lcd.hard_reset().unwrap();
+    lcd.clear().unwrap();
+
+    let (i2c, _, reset) = lcd.release();
+    assert_eq!(reset.levels, [false, true]);
+    assert_eq!(i2c.release().writes[0], [0x00, 0x01]);
+}
+
+#[test]
+fn test_i2c_init_after_hard_reset_restores_glyphs_and_icons() {
+    static GLYPHS: [(char, [u8; 8]); 1] = [('₀', [1, 2, 3, 4, 5, 6, 7, 8])];
+
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay)
+        .with_reset_pin(MockResetPin::default());
+    let degree = lcd.alloc_glyph(&[0x1C, 0x14, 0x1C, 0, 0, 0, 0, 0]).unwrap();
+    lcd.set_icon(0, true).unwrap();
+    lcd.enable_fallback_glyphs(&GLYPHS);
+    lcd.locate(1, 1).unwrap();
+    lcd.write("₀").unwrap();
+
+    lcd.hard_reset().unwrap();
+    lcd.init(Config::default()).unwrap();
+    lcd.write("₀").unwrap();
+    lcd.release_glyph(degree);
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    // The glyph held by the handle and the icons come back before the cursor is homed
+    assert_eq!(writes[8][..6], [0x80, 0x38, 0x80, 0x40, 0x40, 0x1C]);
+    assert_eq!(writes[9][..6], [0x80, 0x39, 0x80, 0x40, 0xC0, 0x10]);
+    assert_eq!(writes[9][writes[9].len() - 2..], [0x00, 0x84]);
+    // The fallback glyph is loaded again
+    assert_eq!(writes[10][..4], [0x80, 0x38, 0x80, 0x48]);
+    assert_eq!(writes[11], [0x40, 0x01]);
+}
+
+#[test]
+fn test_i2c_cursor_off_leaves_extended_bank() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.set_view_mode(ViewMode::Bottom).unwrap();
+    lcd.set_cursor_off().unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0], [0x00, 0x3A, 0x06]);
+    assert_eq!(writes[1], [0x00, 0x38, 0x0C]);
+}
+
+#[test]
+fn test_i2c_execute_skips_redundant_switches() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    let osc = OscillatorSettings::new(OscillatorFreq::Freq540kHz, true);
+    lcd.execute(Instruction::Oscillator(osc)).unwrap();
+    lcd.execute(Instruction::Oscillator(osc)).unwrap();
+    lcd.execute(Instruction::ShiftScrollEnable(ShiftLines::LINE1))
+        .unwrap();
+    lcd.execute(Instruction::SetDdramAddress(0x04)).unwrap();
+    lcd.execute(Instruction::SetCgramAddress(0x00)).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0], [0x00, 0x39, 0x1B]);
+    assert_eq!(writes[1], [0x00, 0x1B]);
+    // IS is kept while RE = 1
+    assert_eq!(writes[2], [0x00, 0x3A, 0x11]);
+    assert_eq!(writes[3], [0x00, 0x39, 0x84]);
+    assert_eq!(writes[4], [0x00, 0x38, 0x40]);
+}
+
+#[test]
+fn test_i2c_init_applies_config() {
+    let config = Config {
+        seg_comm: SegCommControl::new(HorizontalDir::RightToLeft, VerticalDir::TopToBottom),
+        view_mode: ViewMode::Bottom,
+        double_height: Some(DoubleHeight::Lines2),
+        charset: Rom::B,
+        osc_freq_cmd: OscillatorSettings::new(OscillatorFreq::Freq680kHz, true),
+        font_width: FontWidth::SixDot,
+        bw_inversion: false,
+        four_line_enabled: false,
+        ..Config::default()
+    };
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.init(config).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    #[rustfmt::skip]
+    assert_eq!(
+        writes[0],
+        [
+            0x80, 0x3C, // DH set
+            0x80, 0x3A, 0x80, 0x0C, // 6-dot font, NW cleared
+            0x80, 0x07, // bottom view with the segment scan mirrored
+            0x80, 0x1A, // two double height lines
+            0x80, 0x72, 0xC0, 0x04, // ROM B
+            0x00, 0x3D, 0x1F, // 680 kHz
+            0x6C, 0x56, 0x7A, // follower, booster and contrast 42
+        ]
+    );
+    assert_eq!(writes[1], [0x00, 0x06, 0x0C, 0x01]);
+    // Bottom view has no DDRAM offset
+    assert_eq!(writes[3], [0x00, 0x80]);
+}
+
+#[test]
+fn test_i2c_analog_drive() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.set_contrast(0x31).unwrap();
+    assert!(matches!(
+        lcd.set_contrast(64),
+        Err(LcdError::InvalidInputData)
+    ));
+    lcd.set_booster(false).unwrap();
+    lcd.set_follower(FollowerControl::new(Rab::IR6_5p3, true))
+        .unwrap();
+    lcd.set_bias(Bias::OneFourth).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0], [0x00, 0x39, 0x57, 0x71]);
+    // The contrast is kept with the booster
+    assert_eq!(writes[1], [0x00, 0x53]);
+    assert_eq!(writes[2], [0x00, 0x6E]);
+    // BS1 cleared, BS0 set
+    assert_eq!(writes[3], [0x00, 0x38, 0x3A, 0x1C, 0x39, 0x1B]);
+}
+
+#[test]
+fn test_i2c_temperature_compensation() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.set_temperature_coefficient(0x03).unwrap();
+
+    let curve = ContrastCurve::Table(&[(-20, 50), (60, 34)]);
+    let mut compensation = TemperatureCompensation::new(curve, 3);
+    lcd.compensate_contrast(&mut compensation, 0).unwrap();
+    lcd.compensate_contrast(&mut compensation, 2).unwrap();
+    lcd.compensate_contrast(&mut compensation, 5).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes.len(), 3);
+    assert_eq!(writes[0], [0x80, 0x3A, 0x80, 0x76, 0x40, 0x03]);
+    assert_eq!(writes[1], [0x00, 0x39, 0x56, 0x7E]);
+    assert_eq!(writes[2], [0x00, 0x56, 0x7D]);
+}
+
+#[test]
+fn test_i2c_write_encodes_for_active_rom() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.write("25°C").unwrap();
+    lcd.set_rom(Rom::C).unwrap();
+    lcd.write("25°C").unwrap();
+    lcd.write_raw(&[0xDF, 0x00]).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0], [0x40, b'2', b'5', 0xB2, b'C']);
+    assert_eq!(writes[1], [0x80, 0x3A, 0x80, 0x72, 0x40, 0x08]);
+    assert_eq!(writes[2], [0x40, b'2', b'5', 0xDF, b'C']);
+    assert_eq!(writes[3], [0x40, 0xDF, 0x00]);
+}
+
+#[test]
+fn test_i2c_fallback_glyphs() {
+    static GLYPHS: [(char, [u8; 8]); 1] = [('₀', [1, 2, 3, 4, 5, 6, 7, 8])];
+
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.enable_fallback_glyphs(&GLYPHS);
+    lcd.locate(1, 1).unwrap();
+    lcd.write("a₀b").unwrap();
+    lcd.write("₀").unwrap();
+    assert_eq!(lcd.take_stale_cells().next(), None);
+
+    // Taking the slot for a custom character leaves both glyph cells stale
+    lcd.create_custom_char(0, &[0; 8]).unwrap();
+    let mut stale = lcd.take_stale_cells();
+    assert_eq!(stale.next(), Some((1, 2)));
+    assert_eq!(stale.next(), Some((1, 4)));
+    assert_eq!(stale.next(), None);
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[1], [0x40, b'a']);
+    assert_eq!(writes[2][..4], [0x80, 0x40, 0xC0, 1]);
+    assert_eq!(writes[2][16..], [0xC0, 8, 0x00, 0x85]);
+    assert_eq!(writes[3], [0x40, 0x00, b'b']);
+    assert_eq!(writes[4], [0x40, 0x00]);
+}
+
+#[test]
+fn test_i2c_glyph_handles() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.locate(1, 1).unwrap();
+    let degree = lcd.alloc_glyph(&[0x1C, 0x14, 0x1C, 0, 0, 0, 0, 0]).unwrap();
+    let shared = lcd.alloc_glyph(&[0x1C, 0x14, 0x1C, 0, 0, 0, 0, 0]).unwrap();
+    assert_eq!(degree.code(), shared.code());
+    lcd.write_special_char(&degree).unwrap();
+
+    let bars: [_; 7] = core::array::from_fn(|i| lcd.alloc_glyph(&[i as u8; 8]).unwrap());
+    assert!(matches!(
+        lcd.alloc_glyph(&[0x1F; 8]),
+        Err(LcdError::CgramFull)
+    ));
+    lcd.release_glyph(degree);
+    lcd.release_glyph(shared);
+    assert_eq!(lcd.alloc_glyph(&[0x1F; 8]).unwrap().code(), 0);
+    bars.into_iter().for_each(|bar| lcd.release_glyph(bar));
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    // One upload for both handles, then back to the DDRAM cell for the glyph
+    assert_eq!(writes[0], [0x00, 0x84]);
+    assert_eq!(writes[1][..4], [0x80, 0x40, 0xC0, 0x1C]);
+    assert_eq!(writes[1][writes[1].len() - 2..], [0x00, 0x84]);
+    assert_eq!(writes[2], [0x40, 0x00]);
+    assert_eq!(writes[3][..2], [0x80, 0x48]);
+}
+
+#[test]
+fn test_i2c_sleep_and_wake() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.sleep().unwrap();
+    lcd.wake().unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes.len(), 3);
+    assert_eq!(writes[0], [0x00, 0x08, 0x39, 0x52, 0x64, 0x3A, 0x03]);
+    assert_eq!(writes[1], [0x00, 0x02, 0x39, 0x6C, 0x56, 0x7A]);
+    assert_eq!(writes[2], [0x00, 0x0C]);
+}
+
+#[test]
+fn test_i2c_line_shift_and_dot_scroll() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.enable_line_shift(ShiftLines::LINE4).unwrap();
+    lcd.set_scroll_quantity(6).unwrap();
+    assert!(matches!(
+        lcd.set_scroll_quantity(49),
+        Err(LcdError::InvalidInputData)
+    ));
+    lcd.shift_display(HorizontalDir::RightToLeft).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0], [0x00, 0x39, 0x3A, 0x18]);
+    // Dot scroll selected along with the bias bit
+    assert_eq!(writes[1], [0x00, 0x38, 0x3A, 0x1F, 0x86]);
+    // Back to display shift
+    assert_eq!(writes[2], [0x00, 0x1E, 0x38, 0x18]);
+}
+
+#[test]
+fn test_i2c_marquee() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    let mut ticker = Marquee::new(1, "0123456789abcdefgh");
+    ticker.start(&mut lcd).unwrap();
+    ticker.tick(&mut lcd).unwrap();
+    let mut title = Marquee::new(2, "0123456789abcdefghijk").mode(MarqueeMode::Bounce);
+    title.start(&mut lcd).unwrap();
+    title.tick(&mut lcd).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    // Line 1 padded to 20 characters, the part behind the last column wraps around
+    assert_eq!(writes[0], [0x00, 0x39, 0x3A, 0x11]);
+    assert_eq!(writes[1], [0x00, 0x39, 0x84]);
+    assert_eq!(writes[2][1..], *b"0123456789abcdef");
+    assert_eq!(writes[3], [0x00, 0x80]);
+    assert_eq!(writes[4], [0x40, b'g', b'h', b' ', b' ']);
+    assert_eq!(writes[5], [0x00, 0x38, 0x18]);
+    // Longer than the line, redrawn from software
+    assert_eq!(writes[7][1..], *b"0123456789abcdef");
+    assert_eq!(writes[8], [0x00, 0xA4]);
+    assert_eq!(writes[9][1..], *b"123456789abcdefg");
+}
+
+#[test]
+fn test_i2c_double_height_rows() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.set_double_height(Some(DoubleHeight::Lines3Top))
+        .unwrap();
+    lcd.locate(2, 1).unwrap();
+    assert!(matches!(lcd.locate(4, 1), Err(LcdError::InvalidInputData)));
+    assert!(matches!(lcd.clear_line(4), Err(LcdError::InvalidInputData)));
+    lcd.set_double_height(Some(DoubleHeight::Lines2)).unwrap();
+    lcd.locate(2, 1).unwrap();
+    lcd.set_double_height(None).unwrap();
+    lcd.locate(2, 1).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    // DH set by the function set with RE = 0
+    assert_eq!(writes[0], [0x00, 0x3A, 0x1E, 0x3C]);
+    // The double height line covers the first two lines
+    assert_eq!(writes[1], [0x00, 0xC4]);
+    assert_eq!(writes[2], [0x00, 0x3A, 0x1A, 0x3C]);
+    assert_eq!(writes[3], [0x00, 0xC4]);
+    assert_eq!(writes[4], [0x00, 0x3A, 0x1E, 0x38]);
+    assert_eq!(writes[5], [0x00, 0xA4]);
+}
+
+#[test]
+fn test_i2c_inverted_and_data_blink() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.set_inverted(true).unwrap();
+    lcd.set_data_blink(true).unwrap();
+    let bell = lcd.alloc_glyph(&blinking(&[0x04; 8])).unwrap();
+    lcd.set_inverted(false).unwrap();
+    assert!(lcd.config().display_settings.data_blink_enable);
+    lcd.release_glyph(bell);
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    // REV and BE set by the function set with RE = 1
+    assert_eq!(writes[0], [0x00, 0x3B]);
+    assert_eq!(writes[1], [0x00, 0x3F]);
+    // Leaving RE = 1 for the CGRAM address, the glyph rows carry the blink bit
+    assert_eq!(
+        writes[2],
+        [
+            0x80, 0x38, 0x80, 0x40, 0x40, 0x84, 0x84, 0x84, 0x84, 0x84, 0x84, 0x84, 0x84
+        ]
+    );
+    assert_eq!(writes[3], [0x00, 0x3E]);
+}
+
+#[test]
+fn test_i2c_icons() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.locate(1, 1).unwrap();
+    lcd.set_icons_enabled(true).unwrap();
+    lcd.set_icon(7, true).unwrap();
+    lcd.set_icon_blink(9, true).unwrap();
+    assert!(matches!(
+        lcd.set_icon(80, true),
+        Err(LcdError::InvalidInputData)
+    ));
+    lcd.write_icons(&[0x1F; SEGRAM_SIZE]).unwrap();
+    assert!(lcd.icons().is_on(79));
+    lcd.set_contrast(20).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[1], [0x00, 0x39, 0x5E]);
+    // Icon 7 is S3 at SEGRAM address 1, the address counter goes back to DDRAM
+    assert_eq!(writes[2], [0x80, 0x41, 0xC0, 0x04, 0x00, 0x84]);
+    assert_eq!(writes[3], [0x80, 0x41, 0xC0, 0x84, 0x00, 0x84]);
+    assert_eq!(writes[4][..4], [0x80, 0x40, 0xC0, 0x1F]);
+    assert_eq!(writes[4][2 * SEGRAM_SIZE + 2..], [0x00, 0x84]);
+    // The icon display stays enabled
+    assert_eq!(writes[5], [0x00, 0x5D, 0x74]);
+}
+
+#[test]
+fn test_i2c_cursor() {
+    let mut i2c = MockI2c::default();
+    i2c.reads.push(0x47).unwrap();
+    let mut lcd = SSD18030::new_i2c(i2c, 0x3D, NoDelay);
+    // Read from the address counter while no address is tracked
+    assert_eq!(lcd.cursor().unwrap(), Some((3, 4)));
+    lcd.locate(2, 5).unwrap();
+    lcd.write("ab").unwrap();
+    assert_eq!(lcd.cursor().unwrap(), Some((2, 7)));
+    lcd.locate(4, 16).unwrap();
+    lcd.write("x").unwrap();
+    assert_eq!(lcd.cursor().unwrap(), None);
+
+    lcd.set_cursor(true, false).unwrap();
+    assert_eq!(
+        lcd.config().display_control,
+        DisplayConfig::DISPLAY_ON | DisplayConfig::CURSOR_ON
+    );
+    lcd.set_cursor_inverted(false).unwrap();
+    lcd.set_cursor(false, true).unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[5], [0x00, 0x0E]);
+    // B/W is cleared by the extended function set with RE = 1
+    assert_eq!(writes[6], [0x00, 0x3A, 0x09]);
+    assert_eq!(writes[7], [0x00, 0x38, 0x0D]);
+}
+
+#[test]
+fn test_i2c_cursor_outside_ddram() {
+    let mut i2c = MockI2c::default();
+    i2c.reads.push(0x47).unwrap();
+    let mut lcd = SSD18030::new_i2c(i2c, 0x3D, NoDelay);
+    // The address counter points to CGRAM or SEGRAM, so there is no cursor to read
+    lcd.create_custom_char(0, &[0x1F; 8]).unwrap();
+    assert_eq!(lcd.cursor().unwrap(), None);
+    lcd.set_icon(0, true).unwrap();
+    lcd.send_data(&[0x00]).unwrap();
+    assert_eq!(lcd.cursor().unwrap(), None);
+    // The status read still answers once the driver lost track
+    lcd.locate(1, 1).unwrap();
+    lcd.send_data_byte(0x41).unwrap();
+    assert_eq!(lcd.cursor().unwrap(), Some((3, 4)));
+}
+
+#[test]
+fn test_i2c_text_mode() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.set_text_mode(TextMode::StopAtEnd);
+    lcd.locate(1, 15).unwrap();
+    lcd.write("abc\rX\nY").unwrap();
+    lcd.locate(4, 16).unwrap();
+    lcd.write("z").unwrap();
+    lcd.write("dropped").unwrap();
+    lcd.set_text_mode(TextMode::WrapToTop);
+    lcd.write("w").unwrap();
+    assert_eq!(lcd.cursor().unwrap(), Some((1, 2)));
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[1], [0x40, b'a', b'b']);
+    // The row is full, `c` goes to the next row instead of hidden DDRAM
+    assert_eq!(writes[2], [0x00, 0xA4]);
+    assert_eq!(writes[3], [0x40, b'c']);
+    assert_eq!(writes[4], [0x00, 0xA4]);
+    assert_eq!(writes[5], [0x40, b'X']);
+    assert_eq!(writes[6], [0x00, 0xC4]);
+    assert_eq!(writes[7], [0x40, b'Y']);
+    assert_eq!(writes[9], [0x40, b'z']);
+    // Nothing is written past the last row until text may wrap to the top
+    assert_eq!(writes[10], [0x00, 0x84]);
+    assert_eq!(writes[11], [0x40, b'w']);
+}
+
+#[test]
+fn test_i2c_text_mode_after_cgram_access() {
+    for access in 0..3 {
+        let mut i2c = MockI2c::default();
+        if access == 1 {
+            i2c.reads.push(0x1F).unwrap();
+        }
+        // Would put the text in the middle of row 3
+        i2c.reads.push(0x47).unwrap();
+        let mut lcd = SSD18030::new_i2c(i2c, 0x3D, NoDelay);
+        lcd.set_text_mode(TextMode::WrapToTop);
+        match access {
+            0 => lcd.create_custom_char(0, &[0x1F; 8]).unwrap(),
+            1 => lcd.read_custom_char(0, &mut [0; 1]).unwrap(),
+            _ => drop(lcd.alloc_glyph(&[0x1F; 8]).unwrap()),
+        }
+        lcd.write("a").unwrap();
+        assert_eq!(lcd.cursor().unwrap(), Some((1, 2)));
+
+        let (i2c, _, _) = lcd.release();
+        let mock = i2c.release();
+        // The address counter points to CGRAM, so the text starts over in the first row
+        assert_eq!(mock.reads, [0x47]);
+        assert_eq!(
+            mock.writes[mock.writes.len() - 2..],
+            [&[0x00, 0x84][..], &[0x40, b'a']]
+        );
+    }
+}
+
+#[test]
+fn test_i2c_text_mode_after_clear_and_home() {
+    for mode in [TextMode::StopAtEnd, TextMode::WrapToTop] {
+        let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+        lcd.set_text_mode(mode);
+        lcd.clear().unwrap();
+        lcd.write("hello").unwrap();
+        lcd.locate(4, 16).unwrap();
+        lcd.write("z").unwrap();
+        lcd.home().unwrap();
+        lcd.write("again").unwrap();
+
+        let (i2c, _, _) = lcd.release();
+        let writes = i2c.release().writes;
+        // Address 0x00 is hidden in top view, the text starts at the first visible column
+        assert_eq!(writes[2], [0x00, 0x84]);
+        assert_eq!(writes[3], [0x40, b'h', b'e', b'l', b'l', b'o']);
+        assert_eq!(writes[5], [0x40, b'z']);
+        assert_eq!(writes[6], [0x00, 0x02]);
+        assert_eq!(writes[8], [0x00, 0x84]);
+        assert_eq!(writes[9], [0x40, b'a', b'g', b'a', b'i', b'n']);
+    }
+}
+
+#[test]
+fn test_i2c_text_mode_stops_across_writes() {
+    let mut lcd = SSD18030::new_i2c(MockI2c::default(), 0x3D, NoDelay);
+    lcd.set_text_mode(TextMode::StopAtEnd);
+    lcd.locate(4, 16).unwrap();
+    lcd.write("z").unwrap();
+    lcd.write("dropped\n").unwrap();
+    lcd.write("\rx").unwrap();
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[1], [0x40, b'z']);
+    // Back to the first column of the last row
+    assert_eq!(writes[2], [0x00, 0xE4]);
+    assert_eq!(writes[3], [0x40, b'x']);
+    assert_eq!(writes.len(), 4);
+}
+
+#[test]
+fn test_i2c_temperature_compensation_retries() {
+    let i2c = MockI2c {
+        failures: 1,
+        ..MockI2c::default()
+    };
+    let mut lcd = SSD18030::new_i2c(i2c, 0x3D, NoDelay);
+
+    let curve = ContrastCurve::Table(&[(-20, 50), (60, 34)]);
+    let mut compensation = TemperatureCompensation::new(curve, 3);
+    assert!(matches!(
+        lcd.compensate_contrast(&mut compensation, 0),
+        Err(LcdError::Bus(_))
+    ));
+    // Within the hysteresis of the failed reading, but the contrast is still to be set
+    lcd.compensate_contrast(&mut compensation, 1).unwrap();
+    assert_eq!(lcd.config().power_icon_contrast.contrast, 46);
+
+    let (i2c, _, _) = lcd.release();
+    let writes = i2c.release().writes;
+    assert_eq!(writes[0], [0x00, 0x39, 0x56, 0x7E]);
+}
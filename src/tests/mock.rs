@@ -0,0 +1,177 @@
+use core::cell::RefCell;
+use core::convert::Infallible;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{self, OutputPin};
+use embedded_hal::i2c::{self, I2c};
+use embedded_hal::spi::{self, Operation, SpiDevice};
+use heapless::Vec;
+
+/// Delay that returns immediately
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// SPI device recording every written byte, one entry per transaction
+#[derive(Default)]
+pub struct MockSpi {
+    pub frames: Vec<Vec<u8, 64>, 16>,
+}
+
+impl spi::ErrorType for MockSpi {
+    type Error = Infallible;
+}
+
+impl SpiDevice for MockSpi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        let mut frame = Vec::new();
+        for op in operations {
+            if let Operation::Write(bytes) = op {
+                frame.extend_from_slice(bytes).unwrap();
+            }
+        }
+        self.frames.push(frame).unwrap();
+        Ok(())
+    }
+}
+
+pub const PIN_E: usize = 8;
+pub const PIN_RS: usize = 9;
+pub const PIN_RW: usize = 10;
+
+/// Shared line levels of a parallel bus, recording `(rs, data)` on every falling edge of E
+#[derive(Default)]
+pub struct MockBus {
+    pub levels: [bool; 11],
+    pub latched: Vec<(bool, u8), 64>,
+}
+
+/// GPIO pin driving one line of a [`MockBus`]
+pub struct MockPin<'a> {
+    pub line: usize,
+    pub bus: &'a RefCell<MockBus>,
+}
+
+impl digital::ErrorType for MockPin<'_> {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockPin<'_> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        let mut bus = self.bus.borrow_mut();
+        if self.line == PIN_E && bus.levels[PIN_E] {
+            let data = (0..8).fold(0u8, |acc, i| acc | ((bus.levels[i] as u8) << i));
+            let rs = bus.levels[PIN_RS];
+            bus.latched.push((rs, data)).unwrap();
+        }
+        bus.levels[self.line] = false;
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().levels[self.line] = true;
+        Ok(())
+    }
+}
+
+/// I2C bus recording every write, one entry per transaction. Reads are answered from `reads`,
+/// then with zeroes. Addresses listed in `absent` are not acknowledged, and the first `failures`
+/// transactions are lost to bus errors.
+#[derive(Default)]
+pub struct MockI2c {
+    pub writes: Vec<Vec<u8, 128>, 64>,
+    pub reads: Vec<u8, 64>,
+    pub absent: Vec<u8, 4>,
+    pub failures: u8,
+}
+
+impl i2c::ErrorType for MockI2c {
+    type Error = i2c::ErrorKind;
+}
+
+impl I2c for MockI2c {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.absent.contains(&address) {
+            return Err(i2c::ErrorKind::NoAcknowledge(
+                i2c::NoAcknowledgeSource::Address,
+            ));
+        }
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(i2c::ErrorKind::Bus);
+        }
+
+        let mut frame = Vec::new();
+        for op in operations {
+            match op {
+                i2c::Operation::Write(bytes) => frame.extend_from_slice(bytes).unwrap(),
+                i2c::Operation::Read(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = if self.reads.is_empty() {
+                            0
+                        } else {
+                            self.reads.remove(0)
+                        };
+                    }
+                }
+            }
+        }
+        self.writes.push(frame).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for MockI2c {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, operations)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for NoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// Poll a future that never actually waits to completion
+#[cfg(feature = "async")]
+pub fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+    loop {
+        if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Output pin remembering every level it was set to
+#[derive(Default)]
+pub struct MockResetPin {
+    pub levels: Vec<bool, 8>,
+}
+
+impl digital::ErrorType for MockResetPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockResetPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.levels.push(false).unwrap();
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.levels.push(true).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+#[cfg(feature = "async")]
+mod asynchronous;
+mod i2c;
+mod mock;
+mod parallel;
+mod spi;
@@ -0,0 +1,83 @@
+use crate::commands::ViewMode;
+use crate::interface::{Interface, ParallelInterface, ParallelMode};
+use crate::ssd18030_i2c::{Lcd, LcdError, SSD18030};
+use crate::tests::mock::{MockBus, MockPin, NoDelay, PIN_E, PIN_RS, PIN_RW};
+use core::cell::RefCell;
+
+fn pin(bus: &RefCell<MockBus>, line: usize) -> MockPin<'_> {
+    MockPin { line, bus }
+}
+
+#[test]
+fn test_parallel_8bit_write() {
+    let bus = RefCell::new(MockBus::default());
+    let data = core::array::from_fn(|i| pin(&bus, i));
+    let mut par = ParallelInterface::new_8bit(
+        data,
+        pin(&bus, PIN_E),
+        pin(&bus, PIN_RS),
+        pin(&bus, PIN_RW),
+        ParallelMode::M6800,
+        NoDelay,
+    );
+
+    par.send_command(0x01).unwrap();
+    par.send_data(b"Hi").unwrap();
+
+    assert_eq!(
+        bus.borrow().latched,
+        [(false, 0x01), (true, b'H'), (true, b'i')]
+    );
+}
+
+#[test]
+fn test_parallel_4bit_nibbles() {
+    let bus = RefCell::new(MockBus::default());
+    // DB4..DB7 wired to the lowest lines of the mock bus
+    let data = core::array::from_fn(|i| pin(&bus, i));
+    let mut lcd = SSD18030::new(
+        ParallelInterface::new_4bit(
+            data,
+            pin(&bus, PIN_E),
+            pin(&bus, PIN_RS),
+            pin(&bus, PIN_RW),
+            ParallelMode::M6800,
+            NoDelay,
+        ),
+        NoDelay,
+    );
+
+    lcd.set_view_mode(ViewMode::Bottom).unwrap();
+
+    // Function set RE=1 with DL cleared, then the view mode, high nibble first
+    assert_eq!(
+        bus.borrow().latched,
+        [(false, 0x2), (false, 0xA), (false, 0x0), (false, 0x6)]
+    );
+}
+
+#[test]
+fn test_parallel_reads_unsupported() {
+    let bus = RefCell::new(MockBus::default());
+    let data = core::array::from_fn(|i| pin(&bus, i));
+    let mut lcd = SSD18030::new(
+        ParallelInterface::new_8bit(
+            data,
+            pin(&bus, PIN_E),
+            pin(&bus, PIN_RS),
+            pin(&bus, PIN_RW),
+            ParallelMode::M6800,
+            NoDelay,
+        ),
+        NoDelay,
+    );
+
+    // Clear is waited out, the cursor is known from then on
+    lcd.clear().unwrap();
+    assert_eq!(lcd.cursor().unwrap(), None);
+    lcd.locate(2, 3).unwrap();
+    assert_eq!(lcd.cursor().unwrap(), Some((2, 3)));
+
+    lcd.send_command(0x14).unwrap();
+    assert!(matches!(lcd.cursor(), Err(LcdError::ReadUnsupported)));
+}
@@ -0,0 +1,42 @@
+use crate::interface::{Interface, SpiInterface};
+use crate::ssd18030_i2c::{Lcd, LcdError, SSD18030};
+use crate::tests::mock::{MockSpi, NoDelay};
+
+#[test]
+fn test_spi_nibble_split() {
+    // D0..D3 then D4..D7, LSB first on an MSB first bus
+    assert_eq!(SpiInterface::<MockSpi>::split(0x01), [0x80, 0x00]);
+    assert_eq!(SpiInterface::<MockSpi>::split(0x38), [0x10, 0xC0]);
+    assert_eq!(SpiInterface::<MockSpi>::split(0xFF), [0xF0, 0xF0]);
+}
+
+#[test]
+fn test_spi_command_frame() {
+    let mut spi = SpiInterface::new(MockSpi::default());
+    spi.send_command(0x01).unwrap();
+    spi.send_data(b"A").unwrap();
+
+    let frames = spi.release().frames;
+    assert_eq!(frames[0], [0xF8, 0x80, 0x00]);
+    assert_eq!(frames[1], [0xFA, 0x80, 0x20]);
+}
+
+#[test]
+fn test_spi_driver_clear() {
+    let mut lcd = SSD18030::new_spi(MockSpi::default(), NoDelay);
+    lcd.clear().unwrap();
+
+    let (spi, _, _) = lcd.release();
+    assert_eq!(spi.release().frames[0], [0xF8, 0x80, 0x00]);
+}
+
+#[test]
+fn test_spi_write_only() {
+    let mut lcd = SSD18030::new_spi_write_only(MockSpi::default(), NoDelay);
+    lcd.clear().unwrap();
+    assert!(matches!(lcd.read_status(), Err(LcdError::ReadUnsupported)));
+
+    let (spi, _, _) = lcd.release();
+    // Waited out instead of polling the busy flag
+    assert_eq!(spi.release().frames.len(), 1);
+}
@@ -0,0 +1,125 @@
+//! Line layout of text written in a wrapping [`TextMode`](crate::config::TextMode).
+use crate::ssd18030_i2c::COLUMNS;
+
+/// What the driver does next to lay out text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TextStep<'a> {
+    /// Move to the first column of a 1-based row
+    Locate(u8),
+    /// Write characters that fit into the current row
+    Write(&'a str),
+}
+
+/// Steps writing `text` from a row and column on, wrapping at the end of each row
+#[derive(Debug, Clone)]
+pub(crate) struct TextRuns<'a> {
+    rest: &'a str,
+
+    row: u8,
+
+    /// Column of the next character, past the last one while a wrap is pending
+    col: u8,
+
+    /// Rows of the layout
+    rows: u8,
+
+    /// Continue in the first row after the last one instead of dropping the rest
+    wrap_to_top: bool,
+}
+
+impl<'a> TextRuns<'a> {
+    pub(crate) fn new(text: &'a str, (row, col): (u8, u8), rows: u8, wrap_to_top: bool) -> Self {
+        Self {
+            rest: text,
+            row,
+            col,
+            rows,
+            wrap_to_top,
+        }
+    }
+
+    /// Row and column the next text continues at. Past the end of the last row once text was
+    /// dropped.
+    pub(crate) fn position(&self) -> (u8, u8) {
+        (self.row, self.col)
+    }
+}
+
+impl<'a> Iterator for TextRuns<'a> {
+    type Item = TextStep<'a>;
+
+    fn next(&mut self) -> Option<TextStep<'a>> {
+        let c = self.rest.chars().next()?;
+        if c == '\r' {
+            self.rest = &self.rest[1..];
+            self.col = 1;
+            return Some(TextStep::Locate(self.row));
+        }
+        if c == '\n' || self.col > COLUMNS {
+            if c == '\n' {
+                self.rest = &self.rest[1..];
+            }
+            if self.row == self.rows && !self.wrap_to_top {
+                self.rest = "";
+                self.col = COLUMNS + 1;
+                return None;
+            }
+            self.col = 1;
+            self.row = self.row % self.rows + 1;
+            return Some(TextStep::Locate(self.row));
+        }
+
+        let room = COLUMNS + 1 - self.col;
+        let mut end = self.rest.len();
+        let mut chars = 0;
+        for (i, c) in self.rest.char_indices() {
+            if chars == room || matches!(c, '\n' | '\r') {
+                end = i;
+                break;
+            }
+            chars += 1;
+        }
+        let (run, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        self.col += chars;
+        Some(TextStep::Write(run))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TextStep::{Locate, Write};
+
+    #[test]
+    fn test_wrap_and_newlines() {
+        let text = "0123456789abcdefXY\nz\r\n";
+        let steps: heapless::Vec<_, 8> = TextRuns::new(text, (1, 3), 4, false).collect();
+        assert_eq!(
+            steps,
+            [
+                Write("0123456789abcd"),
+                Locate(2),
+                Write("efXY"),
+                Locate(3),
+                Write("z"),
+                Locate(3),
+                Locate(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_past_last_row() {
+        let mut runs = TextRuns::new("ab\ncd", (2, 16), 2, false);
+        let steps: heapless::Vec<_, 4> = runs.by_ref().collect();
+        assert_eq!(steps, [Write("a")]);
+        assert_eq!(runs.position(), (2, 17));
+
+        let steps: heapless::Vec<_, 8> = TextRuns::new("ab\ncd", (2, 16), 2, true).collect();
+        assert_eq!(
+            steps,
+            [Write("a"), Locate(1), Write("b"), Locate(2), Write("cd")]
+        );
+    }
+}